@@ -1,47 +1,188 @@
-use tauri::{AppHandle, Manager};
-use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+use crate::config::cli::AUTOSTARTED_FLAG;
+use crate::config::store::SettingsStore;
+use crate::config::watcher::notify_changes;
+use crate::core::error::{AppError, AppResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_autostart::MacosLauncher;
+#[cfg(not(target_os = "linux"))]
+use tauri_plugin_autostart::{AutoLaunchManager, ManagerExt};
 
 /// 自动启动配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct AutostartConfig {
+    #[serde(with = "macos_launcher")]
+    #[schemars(with = "macos_launcher::Kind")]
     pub launcher: MacosLauncher,
-    pub args: Option<Vec<&'static str>>,
+    pub args: Option<Vec<String>>,
+    /// 自动启动后延迟多少秒再开始后台任务，避免拖慢登录
+    pub delay_secs: u64,
+    /// 自动启动时隐藏所有窗口，只显示托盘
+    pub start_hidden: bool,
+    /// 已有实例在运行时直接退出，不唤起已有实例
+    pub skip_if_running: bool,
 }
 
 impl Default for AutostartConfig {
     fn default() -> Self {
         Self {
             launcher: MacosLauncher::LaunchAgent,
-            args: Some(vec!["--flag1", "--flag2"]),
+            args: Some(vec![AUTOSTARTED_FLAG.into()]),
+            delay_secs: 0,
+            start_hidden: true,
+            skip_if_running: true,
         }
     }
 }
 
-/// 初始化自动启动插件
-pub fn init_autostart_plugin(app: &AppHandle, config: &AutostartConfig) {
-    #[cfg(desktop)]
+/// 向系统注册自动启动的结果
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AutostartStatus {
+    /// 系统中实际的注册状态
+    pub enabled: bool,
+    /// 注册前发现并已修复的差异，例如程序被移动后自动启动文件仍指向旧路径（仅 Linux）
+    pub repaired: Vec<String>,
+}
+
+/// `MacosLauncher` 的序列化方式：`"launch_agent"` 或 `"apple_script"`
+mod macos_launcher {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use tauri_plugin_autostart::MacosLauncher;
+
+    // 仅用于生成 schema
+    /// macOS 自动启动方式
+    #[derive(JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    #[schemars(rename = "MacosLauncher")]
+    #[allow(dead_code)]
+    pub enum Kind {
+        LaunchAgent,
+        AppleScript,
+    }
+
+    pub fn serialize<S: Serializer>(launcher: &MacosLauncher, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(match launcher {
+            MacosLauncher::LaunchAgent => "launch_agent",
+            MacosLauncher::AppleScript => "apple_script",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<MacosLauncher, D::Error> {
+        match String::deserialize(d)?.as_str() {
+            "apple_script" => Ok(MacosLauncher::AppleScript),
+            _ => Ok(MacosLauncher::LaunchAgent),
+        }
+    }
+}
+
+/// 自动启动时传入的参数，总是包含 `--autostarted`，应用据此识别自动启动
+fn launch_args(config: &AutostartConfig) -> Vec<String> {
+    let mut args = config.args.clone().unwrap_or_default();
+    if !args.iter().any(|arg| arg == AUTOSTARTED_FLAG) {
+        args.push(AUTOSTARTED_FLAG.into());
+    }
+    args
+}
+
+/// 根据配置创建自动启动插件（由 `config::plugins` 统一注册）
+pub fn autostart_plugin(config: &AutostartConfig) -> TauriPlugin<Wry> {
+    #[allow(unused_mut)]
+    let mut builder = tauri_plugin_autostart::Builder::new().args(launch_args(config));
+    #[cfg(target_os = "macos")]
     {
-        app.app_handle().plugin(tauri_plugin_autostart::init(
-            config.launcher,
-            config.args.clone(),
-        ));
+        builder = builder.macos_launcher(config.launcher);
     }
+    builder.build()
 }
 
 /// 设置自动启动
-pub fn setup_autostart(
-    app: &AppHandle,
-    enabled: bool,
-) -> Result<bool, Box<dyn std::error::Error>> {
+///
+/// Linux 上直接管理 XDG 自动启动文件（见 `config::xdg_autostart`），不注册插件；
+/// 其他平台使用插件。配置中关闭 `plugins.autostart` 时两者都不可用。
+pub fn setup_autostart(app: &AppHandle, enabled: bool) -> AppResult<AutostartStatus> {
+    #[cfg(target_os = "linux")]
+    {
+        if !app.state::<SettingsStore>().get().plugins.autostart {
+            return Err(AppError::PluginDisabled("autostart"));
+        }
+        setup_xdg_autostart(app, enabled)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        if app.try_state::<AutoLaunchManager>().is_none() {
+            return Err(AppError::PluginDisabled("autostart"));
+        }
+        setup_plugin_autostart(app, enabled)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn setup_plugin_autostart(app: &AppHandle, enabled: bool) -> AppResult<AutostartStatus> {
     let autostart_manager = app.autolaunch();
-    
+
     if enabled {
         autostart_manager.enable()?;
     } else {
         autostart_manager.disable()?;
     }
-    
+
     let is_enabled = autostart_manager.is_enabled()?;
-    println!("registered for autostart? {}", is_enabled);
-    
-    Ok(is_enabled)
+    log::info!("registered for autostart? {}", is_enabled);
+
+    Ok(AutostartStatus {
+        enabled: is_enabled,
+        repaired: Vec::new(),
+    })
+}
+
+/// 写入 `~/.config/autostart/echo.desktop`，关闭时写入 `Hidden=true`
+///
+/// 已有文件与期望内容不一致（例如程序被移动）时重写，并在结果中返回修复的差异。
+#[cfg(target_os = "linux")]
+fn setup_xdg_autostart(app: &AppHandle, enabled: bool) -> AppResult<AutostartStatus> {
+    use crate::config::xdg_autostart::{DesktopEntry, XdgAutostart};
+
+    let backend = XdgAutostart::from_env()
+        .ok_or_else(|| AppError::NotFound("XDG config directory".into()))?;
+    let config = app.state::<SettingsStore>().get().autostart_config;
+    let expected = DesktopEntry {
+        name: app.package_info().name.clone(),
+        exec: std::env::current_exe()?,
+        args: launch_args(&config),
+        delay_secs: config.delay_secs,
+        hidden: !enabled,
+    };
+
+    let repaired: Vec<String> = backend
+        .sync(&expected)?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    for drift in &repaired {
+        log::warn!("{} drifted: {}", backend.path().display(), drift);
+    }
+
+    let is_enabled = backend.is_enabled();
+    log::info!("registered for autostart? {}", is_enabled);
+    Ok(AutostartStatus {
+        enabled: is_enabled,
+        repaired,
+    })
+}
+
+/// 开启或关闭自动启动并持久化到配置
+///
+/// 先向系统注册，成功后才写入配置，再同步托盘菜单并通知前端（不再重复注册）。
+/// 返回系统中实际的注册状态和修复的差异。
+pub fn set_autostart_enabled(app: &AppHandle, enabled: bool) -> AppResult<AutostartStatus> {
+    let registered = setup_autostart(app, enabled)?;
+    let store = app.state::<SettingsStore>();
+    let old = store.get();
+    let new = store.update(|config| config.autostart_enabled = enabled)?;
+    notify_changes(app, &old, &new);
+    Ok(registered)
 }
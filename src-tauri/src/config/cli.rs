@@ -0,0 +1,283 @@
+use crate::config::autostart::AutostartConfig;
+use crate::core::control::ControlCommand;
+use std::path::PathBuf;
+
+/// 自动启动时由启动器传入的参数
+pub const AUTOSTARTED_FLAG: &str = "--autostarted";
+
+/// 支持的日志级别
+pub const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+/// 帮助信息
+const USAGE: &str = "\
+Usage: echo [OPTIONS] [COMMAND]
+
+Commands (控制正在运行的实例):
+  show                     显示主窗口
+  hide                     隐藏主窗口
+  popover <toggle|show|hide>
+                           切换 / 显示 / 隐藏 popover
+  quit                     退出应用
+  status [--json]          显示运行状态
+
+Options:
+      --hidden             启动时不显示主窗口（仅托盘）
+      --minimized          同 --hidden
+      --config <PATH>      使用指定的配置文件
+      --profile <NAME>     使用独立的配置档案
+      --log-level <LEVEL>  日志级别：off, error, warn, info, debug, trace
+      --reset-settings     启动前将配置重置为默认值
+      --autostarted        由系统自动启动（由启动器传入）
+      --json               以 JSON 格式输出（用于 status）
+  -h, --help               显示帮助信息
+  -V, --version            显示版本号";
+
+/// 命令行参数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    pub hidden: bool,
+    pub config: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub log_level: Option<String>,
+    pub reset_settings: bool,
+    pub autostarted: bool,
+    pub json: bool,
+    /// 远程控制子命令，存在时以客户端模式运行
+    pub command: Option<ControlCommand>,
+}
+
+impl CliArgs {
+    /// 解析当前进程的命令行参数
+    ///
+    /// 遇到 `--help` / `--version` 时打印信息并退出；参数错误时打印用法并以状态码 2 退出。
+    pub fn from_env() -> Self {
+        match Self::parse_from(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(CliError::Help) => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            Err(CliError::Version) => {
+                println!("echo {}", env!("CARGO_PKG_VERSION"));
+                std::process::exit(0);
+            }
+            Err(CliError::Invalid(message)) => {
+                eprintln!("error: {}\n\n{}", message, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// 从参数列表解析（不包含程序名）
+    pub fn parse_from<I, S>(args: I) -> Result<Self, CliError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = CliArgs::default();
+        let mut args = args.into_iter().map(Into::into).peekable();
+
+        while let Some(arg) = args.next() {
+            // 支持 `--flag=value` 形式
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            // 下一个参数是选项时不作为值，例如 `--profile --hidden`
+            let mut value = |name: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next_if(|next| !next.starts_with('-')))
+                    .ok_or_else(|| CliError::Invalid(format!("{} requires a value", name)))
+            };
+
+            match flag.as_str() {
+                "--hidden" | "--minimized" | "--autostarted" | "--reset-settings" | "--json"
+                    if inline_value.is_some() =>
+                {
+                    return Err(CliError::Invalid(format!("{} does not take a value", flag)))
+                }
+                "--hidden" | "--minimized" => parsed.hidden = true,
+                "--autostarted" => parsed.autostarted = true,
+                "--reset-settings" => parsed.reset_settings = true,
+                "--json" => parsed.json = true,
+                "--config" => parsed.config = Some(PathBuf::from(value("--config")?)),
+                "--profile" => {
+                    let profile = value("--profile")?;
+                    if profile.is_empty()
+                        || !profile
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        return Err(CliError::Invalid(format!(
+                            "invalid profile name: {}",
+                            profile
+                        )));
+                    }
+                    parsed.profile = Some(profile);
+                }
+                "--log-level" => {
+                    let level = value("--log-level")?.to_ascii_lowercase();
+                    if !LOG_LEVELS.contains(&level.as_str()) {
+                        return Err(CliError::Invalid(format!("invalid log level: {}", level)));
+                    }
+                    parsed.log_level = Some(level);
+                }
+                "-h" | "--help" => return Err(CliError::Help),
+                "-V" | "--version" => return Err(CliError::Version),
+                "show" | "hide" | "quit" | "status" | "popover" if parsed.command.is_none() => {
+                    parsed.command = Some(match flag.as_str() {
+                        "show" => ControlCommand::Show,
+                        "hide" => ControlCommand::Hide,
+                        "quit" => ControlCommand::Quit,
+                        "status" => ControlCommand::Status,
+                        _ => match value("popover")?.as_str() {
+                            "toggle" => ControlCommand::PopoverToggle,
+                            "show" => ControlCommand::PopoverShow,
+                            "hide" => ControlCommand::PopoverHide,
+                            action => {
+                                return Err(CliError::Invalid(format!(
+                                    "unknown popover action: {}",
+                                    action
+                                )))
+                            }
+                        },
+                    });
+                }
+                // macOS 旧版本从 Finder 启动时会传入进程序列号
+                _ if flag.starts_with("-psn_") => {}
+                _ => return Err(CliError::Invalid(format!("unexpected argument: {}", arg))),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// 是否以隐藏状态启动（仅显示托盘）
+    pub fn start_hidden(&self, autostart: &AutostartConfig) -> bool {
+        self.hidden || (self.autostarted && autostart.start_hidden)
+    }
+}
+
+/// 命令行解析结果中的非正常情况
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    Help,
+    Version,
+    Invalid(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, CliError> {
+        CliArgs::parse_from(args.iter().copied())
+    }
+
+    fn invalid(args: &[&str]) -> String {
+        match parse(args) {
+            Err(CliError::Invalid(message)) => message,
+            other => panic!("{:?} parsed as {:?}", args, other),
+        }
+    }
+
+    #[test]
+    fn parses_flags() {
+        let args = parse(&[
+            "--hidden",
+            "--autostarted",
+            "--reset-settings",
+            "--json",
+            "--config",
+            "/tmp/settings.json",
+            "--log-level=DEBUG",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            CliArgs {
+                hidden: true,
+                config: Some(PathBuf::from("/tmp/settings.json")),
+                log_level: Some("debug".into()),
+                reset_settings: true,
+                autostarted: true,
+                json: true,
+                ..CliArgs::default()
+            }
+        );
+        assert!(parse(&["--minimized"]).unwrap().hidden);
+        assert_eq!(parse(&[]).unwrap(), CliArgs::default());
+        assert!(invalid(&["--log-level", "loud"]).contains("invalid log level"));
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        for args in [&["--nope"][..], &["-x"], &["extra"]] {
+            assert!(invalid(args).contains("unexpected argument"), "{:?}", args);
+        }
+        assert_eq!(invalid(&["--hidden=yes"]), "--hidden does not take a value");
+        // macOS 旧版本传入的进程序列号
+        assert_eq!(parse(&["-psn_0_12345"]).unwrap(), CliArgs::default());
+        assert_eq!(parse(&["--help"]), Err(CliError::Help));
+        assert_eq!(parse(&["-V"]), Err(CliError::Version));
+    }
+
+    #[test]
+    fn parses_profile_with_and_without_value() {
+        assert_eq!(
+            parse(&["--profile", "work"]).unwrap().profile.as_deref(),
+            Some("work")
+        );
+        assert_eq!(
+            parse(&["--profile=work_2"]).unwrap().profile.as_deref(),
+            Some("work_2")
+        );
+        assert_eq!(invalid(&["--profile"]), "--profile requires a value");
+        assert_eq!(
+            invalid(&["--profile", "--hidden"]),
+            "--profile requires a value"
+        );
+        assert!(invalid(&["--profile="]).contains("invalid profile name"));
+        assert!(invalid(&["--profile", "../other"]).contains("invalid profile name"));
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        let command = |args: &[&str]| parse(args).unwrap().command;
+        assert_eq!(command(&["show"]), Some(ControlCommand::Show));
+        assert_eq!(command(&["hide"]), Some(ControlCommand::Hide));
+        assert_eq!(command(&["quit"]), Some(ControlCommand::Quit));
+        assert_eq!(
+            command(&["popover", "toggle"]),
+            Some(ControlCommand::PopoverToggle)
+        );
+        assert_eq!(
+            command(&["popover", "show"]),
+            Some(ControlCommand::PopoverShow)
+        );
+        assert_eq!(
+            command(&["popover", "hide"]),
+            Some(ControlCommand::PopoverHide)
+        );
+
+        let status = parse(&["status", "--json"]).unwrap();
+        assert_eq!(status.command, Some(ControlCommand::Status));
+        assert!(status.json);
+
+        assert_eq!(invalid(&["popover"]), "popover requires a value");
+        assert!(invalid(&["popover", "open"]).contains("unknown popover action"));
+        assert!(invalid(&["show", "hide"]).contains("unexpected argument"));
+    }
+
+    #[test]
+    fn autostarted_launches_follow_start_hidden() {
+        let mut autostart = AutostartConfig::default();
+        assert!(parse(&["--autostarted"]).unwrap().start_hidden(&autostart));
+        autostart.start_hidden = false;
+        assert!(!parse(&["--autostarted"]).unwrap().start_hidden(&autostart));
+        assert!(parse(&["--hidden"]).unwrap().start_hidden(&autostart));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::config::cli::AUTOSTARTED_FLAG;
+use serde_json::{Map, Value};
+
+/// 单个迁移步骤：将 vN 的配置对象升级为 vN+1
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// 按顺序排列的迁移链，下标 N 对应 vN -> vN+1
+///
+/// 修改 `AppConfig` / `AutostartConfig` 的结构时，在末尾追加一个迁移函数，
+/// 当前版本号会随之自动递增。
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// 当前配置文件格式版本
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// 迁移结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationOutcome {
+    pub from: u32,
+    pub to: u32,
+}
+
+impl MigrationOutcome {
+    /// 是否实际执行了迁移
+    pub fn migrated(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// 读取配置文件中的版本号，缺失时视为 v0（引入版本号之前写入的文件）
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// 将配置升级到当前版本
+///
+/// 按顺序执行从文件版本到 `CURRENT_VERSION` 之间的所有迁移，
+/// 每一步完成后更新 `version` 字段。比当前版本更新的文件保持不变。
+pub fn migrate(value: &mut Value) -> Result<MigrationOutcome, String> {
+    let from = version_of(value);
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| "settings root is not an object".to_string())?;
+
+    if from >= CURRENT_VERSION {
+        return Ok(MigrationOutcome { from, to: from });
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migration(object)
+            .map_err(|e| format!("migration v{} -> v{}: {}", version, version + 1, e))?;
+        object.insert("version".into(), Value::from(version as u32 + 1));
+    }
+
+    Ok(MigrationOutcome {
+        from,
+        to: CURRENT_VERSION,
+    })
+}
+
+/// v0 -> v1：v0 文件没有版本号，结构与 v1 相同
+fn migrate_v0_to_v1(_config: &mut Map<String, Value>) -> Result<(), String> {
+    Ok(())
+}
+
+/// v1 -> v2：将占位的自动启动参数 `--flag1 --flag2` 替换为 `--autostarted`
+fn migrate_v1_to_v2(config: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(args) = config
+        .get_mut("autostart_config")
+        .and_then(|c| c.get_mut("args"))
+    else {
+        return Ok(());
+    };
+
+    if *args == serde_json::json!(["--flag1", "--flag2"]) {
+        *args = serde_json::json!([AUTOSTARTED_FLAG]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(crate) const V0: &str = include_str!("../../tests/fixtures/settings/v0.json");
+    pub(crate) const V1: &str = include_str!("../../tests/fixtures/settings/v1.json");
+    pub(crate) const FUTURE: &str = include_str!("../../tests/fixtures/settings/future.json");
+
+    fn migrate_fixture(fixture: &str) -> (MigrationOutcome, Value) {
+        let mut value: Value = serde_json::from_str(fixture).unwrap();
+        let outcome = migrate(&mut value).unwrap();
+        (outcome, value)
+    }
+
+    #[test]
+    fn migrates_v0_through_whole_chain() {
+        let (outcome, value) = migrate_fixture(V0);
+        assert_eq!(
+            outcome,
+            MigrationOutcome {
+                from: 0,
+                to: CURRENT_VERSION
+            }
+        );
+        assert_eq!(version_of(&value), CURRENT_VERSION);
+        assert_eq!(value["autostart_enabled"], true);
+        assert_eq!(value["autostart_config"]["args"], json!([AUTOSTARTED_FLAG]));
+    }
+
+    #[test]
+    fn migrates_v1_through_whole_chain() {
+        let (outcome, value) = migrate_fixture(V1);
+        assert_eq!(
+            outcome,
+            MigrationOutcome {
+                from: 1,
+                to: CURRENT_VERSION
+            }
+        );
+        assert_eq!(version_of(&value), CURRENT_VERSION);
+        assert_eq!(value["autostart_config"]["launcher"], "apple_script");
+        assert_eq!(value["autostart_config"]["args"], json!([AUTOSTARTED_FLAG]));
+    }
+
+    #[test]
+    fn keeps_custom_autostart_args() {
+        let mut value = json!({ "version": 1, "autostart_config": { "args": ["--custom"] } });
+        migrate(&mut value).unwrap();
+        assert_eq!(value["autostart_config"]["args"], json!(["--custom"]));
+    }
+
+    #[test]
+    fn leaves_future_versions_alone() {
+        let (outcome, value) = migrate_fixture(FUTURE);
+        assert!(!outcome.migrated());
+        assert_eq!(value, serde_json::from_str::<Value>(FUTURE).unwrap());
+    }
+
+    #[test]
+    fn rejects_non_object_root() {
+        assert!(migrate(&mut json!(["not", "an", "object"])).is_err());
+    }
+}
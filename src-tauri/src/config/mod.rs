@@ -1,2 +1,9 @@
 pub mod autostart;
+pub mod cli;
+pub mod migrations;
+pub mod plugins;
 pub mod setup;
+pub mod store;
+pub mod watcher;
+#[cfg(target_os = "linux")]
+pub mod xdg_autostart;
@@ -0,0 +1,151 @@
+use crate::config::autostart::autostart_plugin;
+use crate::config::setup::AppConfig;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::plugin::Plugin;
+use tauri::{Builder, Wry};
+
+/// 插件开关（可在配置文件中关闭某个插件）
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct PluginsConfig {
+    pub opener: bool,
+    pub autostart: bool,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            opener: true,
+            autostart: true,
+        }
+    }
+}
+
+/// 插件标识
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginId {
+    Opener,
+    Autostart,
+}
+
+impl PluginId {
+    /// 插件名称，与插件自身注册的名称一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            PluginId::Opener => "opener",
+            PluginId::Autostart => "autostart",
+        }
+    }
+}
+
+/// 根据配置列出需要启用的插件
+///
+/// 所有插件都在这里声明，是决定注册哪些插件的唯一位置。
+pub fn enabled_plugins(config: &AppConfig) -> Vec<PluginId> {
+    let mut plugins = Vec::new();
+    if config.plugins.opener {
+        plugins.push(PluginId::Opener);
+    }
+    // 移动端不支持自动启动；Linux 上由 `config::xdg_autostart` 直接管理自动启动文件，
+    // 不注册插件，避免两者写入同一个 `.desktop` 文件
+    #[cfg(all(desktop, not(target_os = "linux")))]
+    if config.plugins.autostart {
+        plugins.push(PluginId::Autostart);
+    }
+    plugins
+}
+
+/// 根据配置创建插件实例
+fn build_plugin(id: PluginId, config: &AppConfig) -> Box<dyn Plugin<Wry>> {
+    match id {
+        PluginId::Opener => Box::new(tauri_plugin_opener::init()),
+        PluginId::Autostart => Box::new(autostart_plugin(&config.autostart_config)),
+    }
+}
+
+/// 已注册插件记录，用于检测重复注册
+///
+/// 记录交给应用状态管理，之后再次注册同一插件（例如运行时按配置重新注册）会被拒绝。
+#[derive(Debug, Default)]
+pub struct PluginRegistry {
+    registered: Mutex<Vec<PluginId>>,
+}
+
+impl PluginRegistry {
+    /// 记录需要注册的插件，返回尚未注册过的插件；重复的插件被跳过并记录错误
+    pub fn claim(&self, ids: impl IntoIterator<Item = PluginId>) -> Vec<PluginId> {
+        let mut registered = self.registered.lock().unwrap();
+        let mut claimed = Vec::new();
+        for id in ids {
+            if registered.contains(&id) {
+                log::error!("plugin `{}` is already registered", id.name());
+                continue;
+            }
+            registered.push(id);
+            claimed.push(id);
+        }
+        claimed
+    }
+
+    /// 已注册的插件
+    pub fn registered(&self) -> Vec<PluginId> {
+        self.registered.lock().unwrap().clone()
+    }
+
+    /// 插件是否已注册
+    pub fn is_registered(&self, id: PluginId) -> bool {
+        self.registered.lock().unwrap().contains(&id)
+    }
+}
+
+/// 将配置中启用的插件注册到 `Builder`，每个插件只初始化一次
+pub fn register_plugins(
+    mut builder: Builder<Wry>,
+    registry: &PluginRegistry,
+    config: &AppConfig,
+) -> Builder<Wry> {
+    for id in registry.claim(enabled_plugins(config)) {
+        builder = builder.plugin_boxed(build_plugin(id, config));
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_plugin_is_initialised_exactly_once() {
+        let config = AppConfig::default();
+        let registry = PluginRegistry::default();
+        let enabled = enabled_plugins(&config);
+        #[cfg(not(target_os = "linux"))]
+        assert_eq!(enabled, vec![PluginId::Opener, PluginId::Autostart]);
+        #[cfg(target_os = "linux")]
+        assert_eq!(enabled, vec![PluginId::Opener]);
+
+        assert_eq!(registry.claim(enabled.clone()), enabled);
+        // 再次注册（或列表中重复出现）时不会再初始化
+        assert!(registry.claim(enabled.clone()).is_empty());
+        assert_eq!(
+            PluginRegistry::default().claim([PluginId::Opener, PluginId::Opener]),
+            vec![PluginId::Opener]
+        );
+        assert_eq!(registry.registered(), enabled);
+    }
+
+    #[test]
+    fn config_disables_plugins() {
+        let mut config = AppConfig::default();
+        config.plugins.autostart = false;
+        assert_eq!(enabled_plugins(&config), vec![PluginId::Opener]);
+
+        let registry = PluginRegistry::default();
+        registry.claim(enabled_plugins(&config));
+        assert!(registry.is_registered(PluginId::Opener));
+        assert!(!registry.is_registered(PluginId::Autostart));
+    }
+}
@@ -1,10 +1,25 @@
 #![allow(unexpected_cfgs)] // 抑制来自 objc crate 宏的警告
 
-use crate::config::autostart::{init_autostart_plugin, setup_autostart, AutostartConfig};
+use crate::config::autostart::{setup_autostart, AutostartConfig};
+use crate::config::cli::CliArgs;
+use crate::config::plugins::PluginsConfig;
+use crate::config::store::SettingsStore;
+use crate::config::watcher::watch_settings;
+use crate::core::capabilities::register_capabilities;
+use crate::core::crash;
+use crate::core::error::AppResult;
 use crate::core::events::EventEmitter;
-use crate::ui::menu::setup_menu;
-use crate::ui::tray::{setup_tray, hide_dock_icon};
-use tauri::{App, Manager};
+use crate::core::instance::InstanceGuard;
+use crate::core::logger;
+use crate::ui::menu::{setup_menu, show_crash_reports};
+use crate::ui::tray::{hide_dock_icon, setup_tray};
+use crate::ui::tray_theme::watch_theme;
+use crate::ui::window_state::{self, WindowStateStore, WINDOW_STATE_FILE_NAME};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::Duration;
+use tauri::{App, AppHandle, Manager};
 
 #[cfg(target_os = "macos")]
 use cocoa::appkit::NSWindow;
@@ -12,41 +27,55 @@ use cocoa::appkit::NSWindow;
 use cocoa::base::id;
 
 /// 应用初始化配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct AppConfig {
-    pub autostart_enabled: bool,
+    pub autostart_enabled: bool, // 默认不启用自动启动
     pub autostart_config: AutostartConfig,
+    pub plugins: PluginsConfig,
 }
 
-impl Default for AppConfig {
-    fn default() -> AppConfig {
-        AppConfig {
-            autostart_enabled: false, // 默认不启用自动启动
-            autostart_config: AutostartConfig::default(),
-        }
+/// 执行应用初始化设置
+pub fn setup_app(app: &mut App) -> AppResult<()> {
+    let cli = app.state::<CliArgs>().inner().clone();
+    // 配置在创建应用之前已加载（见 `lib.rs`），插件也已按配置注册
+    let config = app.state::<SettingsStore>().get();
+    let hidden = cli.start_hidden(&config.autostart_config);
+    if cli.autostarted {
+        log::info!(
+            "launched by autostart (hidden: {}, delay: {}s)",
+            hidden,
+            config.autostart_config.delay_secs
+        );
     }
-}
 
+    // 日志写入应用日志目录
+    if let Err(err) = logger::attach_log_dir(&app.path().app_log_dir()?) {
+        log::error!("failed to open log file: {}", err);
+    }
 
-/// 执行应用初始化设置
-pub fn setup_app(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
-    let config = AppConfig::default();
+    // 弹出窗口、关于窗口等的权限（主窗口见 capabilities/default.json）
+    register_capabilities(app.handle())?;
+
+    // 窗口位置和尺寸与配置文件保存在同一目录，随 profile 区分
+    let state_path = app
+        .state::<SettingsStore>()
+        .path()
+        .with_file_name(WINDOW_STATE_FILE_NAME);
+    app.manage(WindowStateStore::load(state_path));
 
     // 设置菜单
     setup_menu(app.handle())?;
 
-    // 初始化自动启动插件
-    init_autostart_plugin(app.handle(), &config.autostart_config);
-
-    // 设置自动启动（根据配置决定是否启用）
-    if config.autostart_enabled {
-        let _ = setup_autostart(app.handle(), true)?;
-    }
-
     // 设置系统托盘
     setup_tray(app.handle())?;
 
     // 监听主窗口关闭事件，隐藏窗口而不是退出应用
+    // 先恢复并跟踪窗口状态，保证隐藏之前记录关闭时的位置
     if let Some(window) = app.get_webview_window("main") {
+        window_state::track_window(&window);
+        // 系统主题变化时切换托盘图标
+        watch_theme(&window);
         let window_clone = window.clone();
         let app_handle = app.handle().clone();
         window.on_window_event(move |event| {
@@ -54,28 +83,49 @@ pub fn setup_app(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
                 // 点击关闭按钮时隐藏窗口而不是退出应用
                 // 这样应用会在后台继续运行（配合系统托盘）
                 window_clone.hide().unwrap();
-                
+
                 // 在 macOS 上隐藏 Dock 栏图标
                 #[cfg(target_os = "macos")]
                 {
                     hide_dock_icon();
                 }
-                
+
                 api.prevent_close();
             }
         });
     }
 
+    // 主窗口默认不可见，除非以隐藏模式启动（--hidden，或自动启动且配置了 `start_hidden`）
+    if hidden {
+        #[cfg(target_os = "macos")]
+        {
+            hide_dock_icon();
+        }
+    } else if let Some(window) = app.get_webview_window("main") {
+        window.show()?;
+        window.set_focus()?;
+    }
+
+    // 上次运行崩溃时，显示崩溃报告（隐藏启动时留到下次正常启动）
+    let pending = crash::pending_reports();
+    if !pending.is_empty() {
+        log::warn!("found {} pending crash report(s)", pending.len());
+        if !hidden {
+            show_crash_reports(app.handle());
+        }
+    }
+
     // 监听 popover 窗口失焦事件，点击外部区域时隐藏
     if let Some(popover_window) = app.get_webview_window("popover") {
+        window_state::track_window(&popover_window);
         let popover_clone = popover_window.clone();
-        
+
         // 在 macOS 上设置窗口圆角
         #[cfg(target_os = "macos")]
         {
             set_window_rounded_corners(&popover_window);
         }
-        
+
         popover_window.on_window_event(move |event| {
             if let tauri::WindowEvent::Focused(false) = event {
                 // 窗口失去焦点时隐藏 popover
@@ -84,44 +134,77 @@ pub fn setup_app(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
         });
     }
 
-    // 初始化事件发射器
-    let emitter = EventEmitter::new(app.handle().clone());
-    app.manage(emitter);
+    // 初始化事件发射器，各窗口通过 `subscribe_events` 订阅
+    app.manage(EventEmitter::new());
+
+    // 接收其他实例转发的启动参数
+    app.state::<InstanceGuard>().serve(app.handle());
+
+    // 自动启动时延迟开始后台任务，避免拖慢登录
+    let delay = if cli.autostarted {
+        config.autostart_config.delay_secs
+    } else {
+        0
+    };
+    let app_handle = app.handle().clone();
+    if delay == 0 {
+        start_background_work(&app_handle);
+    } else {
+        log::info!("delaying background work by {}s", delay);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(delay));
+            start_background_work(&app_handle);
+        });
+    }
+
+    Ok(())
+}
+
+/// 启动后台任务：注册自动启动、监听配置文件变化等
+fn start_background_work(app: &AppHandle) {
+    let config = app.state::<SettingsStore>().get();
+
+    // 设置自动启动（根据配置决定是否启用）
+    if config.autostart_enabled {
+        if let Err(err) = setup_autostart(app, true) {
+            log::error!("failed to register autostart: {}", err);
+        }
+    }
+
+    // 监听配置文件变化（手动编辑或多设备同步）
+    watch_settings(app);
 
-    // 可以在这里启动后台任务，定期发送事件
-    // let app_handle = app.handle().clone();
+    // 可以在这里启动其他后台任务，定期发送事件
     // tauri::async_runtime::spawn(async move {
     //     // 后台任务逻辑
     // });
-
-    Ok(())
 }
 
 /// 在 macOS 上设置窗口圆角
 #[cfg(target_os = "macos")]
 fn set_window_rounded_corners(window: &tauri::WebviewWindow) {
     use objc::*;
-    
+
     unsafe {
         // 获取窗口的 NSWindow 对象
         // ns_window() 返回 *mut c_void，需要转换为 id
         if let Ok(ns_window_ptr) = window.ns_window() {
             let ns_window: id = ns_window_ptr as id;
-            
+
             if !ns_window.is_null() {
                 // 获取窗口的 contentView
                 let content_view: id = msg_send![ns_window, contentView];
                 if !content_view.is_null() {
                     // 启用 layer-backed view
                     let _: () = msg_send![content_view, setWantsLayer: cocoa::base::YES];
-                    
+
                     // 获取或创建 layer
                     let layer: id = msg_send![content_view, layer];
                     if !layer.is_null() {
                         // 设置圆角半径（16.0 对应 CSS 中的 16px）
                         let corner_radius: f64 = 16.0;
                         let _: () = msg_send![layer, setCornerRadius: corner_radius];
-                        
+
                         // 启用遮罩到边界，确保内容被裁剪到圆角边界内
                         let _: () = msg_send![layer, setMasksToBounds: cocoa::base::YES];
                     }
@@ -0,0 +1,448 @@
+use crate::config::autostart::AutostartConfig;
+use crate::config::cli::CliArgs;
+use crate::config::migrations;
+use crate::config::setup::AppConfig;
+use crate::core::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 配置文件名（位于应用配置目录下）
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// 当前配置文件格式版本
+pub const SETTINGS_VERSION: u32 = migrations::CURRENT_VERSION;
+
+/// 磁盘上的配置文件结构：版本号 + 配置内容
+#[derive(Debug, Serialize, Deserialize)]
+struct SettingsFile {
+    version: u32,
+    #[serde(flatten)]
+    config: AppConfig,
+}
+
+/// 持久化配置存储
+///
+/// 启动时从磁盘加载 `AppConfig`，修改时原子写回。
+/// 文件缺失或损坏时回退到默认配置。
+/// 文件来自更新的版本时，写回会保留其中本版本不认识的字段和版本号，降级后再升级不会丢失配置。
+pub struct SettingsStore {
+    path: PathBuf,
+    config: Mutex<AppConfig>,
+    /// 更新版本的配置文件原文，没有时为 `None`
+    newer: Mutex<Option<Value>>,
+}
+
+impl SettingsStore {
+    /// 从指定路径加载配置，失败时使用默认配置
+    pub fn load(path: PathBuf) -> Self {
+        let (config, newer) = read_settings(&path);
+        Self {
+            path,
+            config: Mutex::new(config),
+            newer: Mutex::new(newer),
+        }
+    }
+
+    /// 配置文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 获取当前配置的副本
+    pub fn get(&self) -> AppConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// 替换当前配置并写回磁盘
+    pub fn set(&self, config: AppConfig) -> io::Result<()> {
+        let mut current = self.config.lock().unwrap();
+        write_settings(&self.path, &config, self.newer.lock().unwrap().as_ref())?;
+        *current = config;
+        Ok(())
+    }
+
+    /// 从磁盘重新加载配置
+    ///
+    /// 内容与内存中的配置不同时更新内存并返回 `(旧配置, 新配置)`；
+    /// 文件缺失、无法解析或内容未变化时返回 `None`，保留当前配置。
+    pub fn reload(&self) -> Option<(AppConfig, AppConfig)> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let (config, newer) = match parse_settings(&self.path, &contents) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                log::warn!("ignoring invalid settings {}: {}", self.path.display(), err);
+                return None;
+            }
+        };
+
+        let mut current = self.config.lock().unwrap();
+        *self.newer.lock().unwrap() = newer;
+        if changed_keys(&current, &config).is_empty() {
+            return None;
+        }
+        let old = std::mem::replace(&mut *current, config.clone());
+        Some((old, config))
+    }
+
+    /// 就地修改配置并写回磁盘，返回修改后的配置
+    pub fn update<F>(&self, f: F) -> io::Result<AppConfig>
+    where
+        F: FnOnce(&mut AppConfig),
+    {
+        let mut current = self.config.lock().unwrap();
+        let mut config = current.clone();
+        f(&mut config);
+        write_settings(&self.path, &config, self.newer.lock().unwrap().as_ref())?;
+        *current = config.clone();
+        Ok(config)
+    }
+}
+
+/// 解析配置文件路径
+///
+/// 优先使用 `--config` 指定的路径；指定 `--profile` 时使用
+/// `<配置目录>/profiles/<名称>/settings.json`；否则使用 `<配置目录>/settings.json`。
+/// 配置目录与 `PathResolver::app_config_dir` 相同，但可以在应用创建之前解析，
+/// 以便根据配置注册插件。无法确定配置目录时返回 `AppError::Config`。
+pub fn resolve_settings_path(identifier: &str, cli: &CliArgs) -> AppResult<PathBuf> {
+    if let Some(path) = &cli.config {
+        return Ok(path.clone());
+    }
+
+    let mut dir = dirs::config_dir()
+        .ok_or_else(|| AppError::Config("failed to resolve app config dir".into()))?
+        .join(identifier);
+    if let Some(profile) = &cli.profile {
+        dir = dir.join("profiles").join(profile);
+    }
+    Ok(dir.join(SETTINGS_FILE_NAME))
+}
+
+/// 只读取自动启动配置中的 `skip_if_running`，不迁移、不备份也不重命名配置文件
+///
+/// 在单实例检查之前调用：此时还不确定当前进程是否为主实例，不能修改配置文件。
+/// 文件缺失、无法解析或没有该字段时使用默认值。
+pub fn peek_skip_if_running(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+        .and_then(|value| {
+            value
+                .pointer("/autostart_config/skip_if_running")
+                .and_then(Value::as_bool)
+        })
+        .unwrap_or(AutostartConfig::default().skip_if_running)
+}
+
+/// 比较两份配置，返回发生变化的顶层字段名
+pub fn changed_keys(old: &AppConfig, new: &AppConfig) -> Vec<String> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// 读取配置文件，返回配置和更新版本的文件原文（见 [`parse_settings`]）
+///
+/// 文件不存在时返回默认配置；旧版本文件会先备份再迁移到当前版本并写回；
+/// 文件损坏或迁移失败时将其备份为 `*.corrupt`，避免下次保存时覆盖用户数据，
+/// 然后返回默认配置。
+fn read_settings(path: &Path) -> (AppConfig, Option<Value>) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return (AppConfig::default(), None),
+        Err(err) => {
+            log::error!("failed to read settings {}: {}", path.display(), err);
+            return (AppConfig::default(), None);
+        }
+    };
+
+    match parse_settings(path, &contents) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            log::error!("settings file {} is corrupt: {}", path.display(), err);
+            let _ = fs::rename(path, path.with_extension("json.corrupt"));
+            (AppConfig::default(), None)
+        }
+    }
+}
+
+/// 解析配置内容，必要时执行迁移
+///
+/// 文件版本比当前版本新时不迁移，同时返回文件原文，写回时用于保留未知字段。
+fn parse_settings(path: &Path, contents: &str) -> Result<(AppConfig, Option<Value>), String> {
+    let mut value: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let version = migrations::version_of(&value);
+    let newer = (version > SETTINGS_VERSION).then(|| value.clone());
+    if newer.is_some() {
+        log::warn!(
+            "settings file {} has newer version v{} (current v{})",
+            path.display(),
+            version,
+            SETTINGS_VERSION
+        );
+    }
+
+    let outcome = migrations::migrate(&mut value)?;
+    let file: SettingsFile = serde_json::from_value(value).map_err(|e| e.to_string())?;
+
+    if outcome.migrated() {
+        // 保留迁移前的原始文件，备份失败时不写回，仅在内存中使用迁移结果
+        let backup = backup_path(path, outcome.from);
+        if let Err(err) = fs::write(&backup, contents) {
+            log::error!(
+                "failed to back up settings to {}: {}",
+                backup.display(),
+                err
+            );
+        } else if let Err(err) = write_settings(path, &file.config, None) {
+            log::error!("failed to write migrated settings: {}", err);
+        } else {
+            log::info!(
+                "migrated settings v{} -> v{}, backup at {}",
+                outcome.from,
+                outcome.to,
+                backup.display()
+            );
+        }
+    }
+
+    Ok((file.config, newer))
+}
+
+/// 迁移前备份文件路径，例如 `settings.json.v1.bak`
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    path.with_extension(format!("json.v{}.bak", version))
+}
+
+/// 原子写入配置文件
+///
+/// 先写入同目录下的临时文件并刷盘，再重命名覆盖目标文件，
+/// 保证任何时刻磁盘上的配置文件都是完整的。
+/// `newer` 为更新版本的文件原文，配置合并到其中写回，保留原文的版本号和未知字段。
+fn write_settings(path: &Path, config: &AppConfig, newer: Option<&Value>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let file = SettingsFile {
+        version: SETTINGS_VERSION,
+        config: config.clone(),
+    };
+    let json = match newer {
+        Some(newer) => {
+            let mut value = newer.clone();
+            let mut known = serde_json::to_value(&file).map_err(io::Error::other)?;
+            if let Some(known) = known.as_object_mut() {
+                known.remove("version");
+            }
+            merge(&mut value, known);
+            serde_json::to_string_pretty(&value)
+        }
+        None => serde_json::to_string_pretty(&file),
+    }
+    .map_err(io::Error::other)?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    {
+        let mut tmp = fs::File::create(&tmp_path)?;
+        tmp.write_all(json.as_bytes())?;
+        tmp.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+/// 把 `patch` 合并到 `target`：对象按字段递归合并，其他值直接替换
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::cli::AUTOSTARTED_FLAG;
+    use crate::config::migrations::tests::{FUTURE, V0, V1};
+
+    fn temp_settings(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("echo-store-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(SETTINGS_FILE_NAME)
+    }
+
+    fn cleanup(path: &Path) {
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn set_and_update_round_trip() {
+        let path = temp_settings("round-trip");
+        let store = SettingsStore::load(path.clone());
+        assert!(!store.get().autostart_enabled);
+
+        let mut config = store.get();
+        config.autostart_enabled = true;
+        store.set(config).unwrap();
+        let updated = store
+            .update(|config| config.autostart_config.delay_secs = 30)
+            .unwrap();
+        assert!(updated.autostart_enabled);
+
+        let reloaded = SettingsStore::load(path.clone()).get();
+        assert!(reloaded.autostart_enabled);
+        assert_eq!(reloaded.autostart_config.delay_secs, 30);
+        assert!(changed_keys(&updated, &reloaded).is_empty());
+
+        let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value["version"], SETTINGS_VERSION);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn writes_atomically() {
+        let path = temp_settings("atomic");
+        // 父目录不存在时自动创建
+        write_settings(&path, &AppConfig::default(), None).unwrap();
+        let config = AppConfig {
+            autostart_enabled: true,
+            ..AppConfig::default()
+        };
+        write_settings(&path, &config, None).unwrap();
+
+        assert!(!path.with_extension("json.tmp").exists());
+        let entries = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(entries, 1);
+        assert!(read_settings(&path).0.autostart_enabled);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn renames_corrupt_file() {
+        let path = temp_settings("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{ not json").unwrap();
+
+        let store = SettingsStore::load(path.clone());
+        assert!(!store.get().autostart_enabled);
+        assert!(!path.exists());
+        let corrupt = path.with_extension("json.corrupt");
+        assert_eq!(fs::read_to_string(corrupt).unwrap(), "{ not json");
+
+        // 下次保存写入新文件，不覆盖备份
+        store
+            .update(|config| config.autostart_enabled = true)
+            .unwrap();
+        assert!(read_settings(&path).0.autostart_enabled);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn reload_reports_changes_only() {
+        let path = temp_settings("reload");
+        let store = SettingsStore::load(path.clone());
+        store.set(AppConfig::default()).unwrap();
+        assert!(store.reload().is_none());
+
+        let config = AppConfig {
+            autostart_enabled: true,
+            ..AppConfig::default()
+        };
+        write_settings(&path, &config, None).unwrap();
+        let (old, new) = store.reload().unwrap();
+        assert_eq!(changed_keys(&old, &new), vec!["autostart_enabled"]);
+        assert!(store.get().autostart_enabled);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn backs_up_and_rewrites_old_versions() {
+        for (version, fixture) in [(0, V0), (1, V1)] {
+            let path = temp_settings(&format!("migrate-v{}", version));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, fixture).unwrap();
+
+            let config = SettingsStore::load(path.clone()).get();
+            assert_eq!(
+                config.autostart_config.args,
+                Some(vec![AUTOSTARTED_FLAG.to_string()])
+            );
+
+            let backup = path.with_file_name(format!("settings.json.v{}.bak", version));
+            assert_eq!(fs::read_to_string(backup).unwrap(), fixture);
+            let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(value["version"], SETTINGS_VERSION);
+            cleanup(&path);
+        }
+    }
+
+    #[test]
+    fn leaves_future_versions_on_disk() {
+        let path = temp_settings("future");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, FUTURE).unwrap();
+
+        let config = SettingsStore::load(path.clone()).get();
+        assert!(config.autostart_enabled);
+        assert_eq!(fs::read_to_string(&path).unwrap(), FUTURE);
+        let entries = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(entries, 1);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn keeps_unknown_fields_of_future_versions_when_saving() {
+        let path = temp_settings("future-save");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, FUTURE).unwrap();
+
+        let store = SettingsStore::load(path.clone());
+        store
+            .update(|config| config.autostart_config.delay_secs = 30)
+            .unwrap();
+        let value: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value["version"], 99);
+        assert_eq!(value["added_in_v99"], serde_json::json!({}));
+        assert_eq!(value["autostart_config"]["delay_secs"], 30);
+        assert_eq!(value["autostart_config"]["launcher"], "launch_agent");
+        assert_eq!(store.get().autostart_config.delay_secs, 30);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn peeking_skip_if_running_leaves_the_file_alone() {
+        let path = temp_settings("peek");
+        assert!(peek_skip_if_running(&path));
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, V1).unwrap();
+        assert!(peek_skip_if_running(&path));
+        assert_eq!(fs::read_to_string(&path).unwrap(), V1);
+        assert!(!backup_path(&path, 1).exists());
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(peek_skip_if_running(&path));
+        assert!(path.exists());
+        assert!(!path.with_extension("json.corrupt").exists());
+
+        let contents = r#"{ "version": 2, "autostart_config": { "skip_if_running": false } }"#;
+        fs::write(&path, contents).unwrap();
+        assert!(!peek_skip_if_running(&path));
+        cleanup(&path);
+    }
+}
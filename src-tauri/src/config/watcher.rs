@@ -0,0 +1,133 @@
+use crate::config::autostart::setup_autostart;
+use crate::config::setup::AppConfig;
+use crate::config::store::{changed_keys, SettingsStore};
+use crate::core::error::AppResult;
+use crate::core::events::{ConfigChangedPayload, EventEmitter};
+use crate::ui::tray::sync_autostart_item;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+/// 配置文件轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 文件指纹：修改时间 + 大小
+type Fingerprint = Option<(SystemTime, u64)>;
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// 启动配置文件监听
+///
+/// 在后台线程中轮询配置文件，检测到变化时重新解析并与内存中的配置比较，
+/// 有差异时执行副作用并向前端发送 `config-changed` 事件。
+/// 应用自身写入的内容与内存一致，不会产生事件。
+pub fn watch_settings(app: &AppHandle) {
+    let app = app.clone();
+    let path = app.state::<SettingsStore>().path().to_path_buf();
+
+    thread::spawn(move || {
+        let mut last = fingerprint(&path);
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let current = fingerprint(&path);
+            if current == last {
+                continue;
+            }
+            last = current;
+
+            if let Some((old, new)) = app.state::<SettingsStore>().reload() {
+                apply_changes(&app, &old, &new);
+            }
+        }
+    });
+}
+
+/// 应用配置变更
+///
+/// 根据新旧配置的差异执行副作用（如切换自动启动），并发送 `config-changed` 事件。
+/// 文件监听使用：文件已经被修改，注册失败只记录日志。
+/// 前端修改配置时注册失败需要回滚，分别调用 `register_autostart` 和 `notify_changes`。
+pub fn apply_changes(app: &AppHandle, old: &AppConfig, new: &AppConfig) {
+    if let Err(err) = register_autostart(app, old, new) {
+        log::error!("failed to apply autostart setting: {}", err);
+    }
+    notify_changes(app, old, new);
+}
+
+/// 配置变化需要时向系统重新注册自动启动
+///
+/// 注册时读取已保存的配置（参数、延迟），需要在新配置写入之后调用。
+pub fn register_autostart(app: &AppHandle, old: &AppConfig, new: &AppConfig) -> AppResult<()> {
+    if let Some(enabled) = autostart_registration(old, new) {
+        setup_autostart(app, enabled)?;
+    }
+    Ok(())
+}
+
+/// 同步托盘菜单并发送 `config-changed` 事件，不向系统注册自动启动
+///
+/// 调用方已经注册过自动启动时使用（见 `set_autostart_enabled`），避免重复注册。
+pub fn notify_changes(app: &AppHandle, old: &AppConfig, new: &AppConfig) {
+    let changed = changed_keys(old, new);
+    if changed.is_empty() {
+        return;
+    }
+
+    if old.autostart_enabled != new.autostart_enabled {
+        sync_autostart_item(app, new.autostart_enabled);
+    }
+
+    if let Some(emitter) = app.try_state::<EventEmitter>() {
+        let _ = emitter.emit_config_changed(ConfigChangedPayload {
+            config: new.clone(),
+            changed,
+        });
+    }
+}
+
+/// 配置变化需要向系统重新注册自动启动时，返回应注册的状态
+///
+/// 开关变化时按新状态注册；已开启时参数或延迟变化也需要重新注册。
+fn autostart_registration(old: &AppConfig, new: &AppConfig) -> Option<bool> {
+    if old.autostart_enabled != new.autostart_enabled {
+        Some(new.autostart_enabled)
+    } else if new.autostart_enabled
+        && changed_keys(old, new)
+            .iter()
+            .any(|key| key == "autostart_config")
+    {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_autostart_only_when_needed() {
+        let off = AppConfig::default();
+        let on = AppConfig {
+            autostart_enabled: true,
+            ..AppConfig::default()
+        };
+        assert_eq!(autostart_registration(&off, &on), Some(true));
+        assert_eq!(autostart_registration(&on, &off), Some(false));
+        assert_eq!(autostart_registration(&on, &on), None);
+
+        let mut delayed = on.clone();
+        delayed.autostart_config.delay_secs = 30;
+        assert_eq!(autostart_registration(&on, &delayed), Some(true));
+        let mut delayed_off = off.clone();
+        delayed_off.autostart_config.delay_secs = 30;
+        assert_eq!(autostart_registration(&off, &delayed_off), None);
+    }
+}
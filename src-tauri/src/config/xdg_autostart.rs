@@ -0,0 +1,562 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 自动启动文件名（位于 `$XDG_CONFIG_HOME/autostart` 下）
+pub const DESKTOP_FILE_NAME: &str = "echo.desktop";
+
+const GROUP_HEADER: &str = "[Desktop Entry]";
+
+/// 自动启动 `.desktop` 文件中我们关心的字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    pub name: String,
+    /// 可执行文件路径
+    pub exec: PathBuf,
+    /// 启动参数（不包含可执行文件）
+    pub args: Vec<String>,
+    /// `X-GNOME-Autostart-Delay`，0 表示不写入
+    pub delay_secs: u64,
+    /// `Hidden=true` 表示禁用（桌面环境会忽略该文件）
+    pub hidden: bool,
+}
+
+/// 已写入的文件与期望内容不一致的地方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drift {
+    /// `Exec` 指向的程序与当前程序不同（程序被移动或重新安装到了别处）
+    ExecMoved {
+        expected: PathBuf,
+        found: PathBuf,
+    },
+    ArgsChanged {
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+    DelayChanged {
+        expected: u64,
+        found: u64,
+    },
+    HiddenChanged {
+        expected: bool,
+        found: bool,
+    },
+    /// 文件存在但无法解析
+    Invalid(String),
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::ExecMoved { expected, found } => write!(
+                f,
+                "Exec points to {} but the app is at {}",
+                found.display(),
+                expected.display()
+            ),
+            Drift::ArgsChanged { expected, found } => {
+                write!(f, "arguments are {:?}, expected {:?}", found, expected)
+            }
+            Drift::DelayChanged { expected, found } => {
+                write!(f, "delay is {}s, expected {}s", found, expected)
+            }
+            Drift::HiddenChanged { expected, found } => {
+                write!(f, "Hidden is {}, expected {}", found, expected)
+            }
+            Drift::Invalid(err) => write!(f, "file is invalid: {}", err),
+        }
+    }
+}
+
+impl DesktopEntry {
+    /// 与期望的内容比较，返回所有差异
+    pub fn drift(&self, expected: &DesktopEntry) -> Vec<Drift> {
+        let mut drift = Vec::new();
+        if self.exec != expected.exec {
+            drift.push(Drift::ExecMoved {
+                expected: expected.exec.clone(),
+                found: self.exec.clone(),
+            });
+        }
+        if self.args != expected.args {
+            drift.push(Drift::ArgsChanged {
+                expected: expected.args.clone(),
+                found: self.args.clone(),
+            });
+        }
+        if self.delay_secs != expected.delay_secs {
+            drift.push(Drift::DelayChanged {
+                expected: expected.delay_secs,
+                found: self.delay_secs,
+            });
+        }
+        if self.hidden != expected.hidden {
+            drift.push(Drift::HiddenChanged {
+                expected: expected.hidden,
+                found: self.hidden,
+            });
+        }
+        drift
+    }
+
+    /// 生成 `.desktop` 文件内容
+    pub fn render(&self) -> String {
+        let exec = std::iter::once(self.exec.to_string_lossy().into_owned())
+            .chain(self.args.iter().cloned())
+            .map(|arg| quote_arg(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut out = String::new();
+        out.push_str(GROUP_HEADER);
+        out.push('\n');
+        out.push_str("Type=Application\n");
+        out.push_str("Version=1.0\n");
+        out.push_str(&format!("Name={}\n", escape_value(&self.name)));
+        out.push_str(&format!("Exec={}\n", escape_value(&exec)));
+        out.push_str("Terminal=false\n");
+        out.push_str(&format!("Hidden={}\n", self.hidden));
+        out.push_str(&format!("X-GNOME-Autostart-enabled={}\n", !self.hidden));
+        if self.delay_secs > 0 {
+            out.push_str(&format!("X-GNOME-Autostart-Delay={}\n", self.delay_secs));
+        }
+        out
+    }
+
+    /// 解析并校验 `.desktop` 文件内容
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut in_group = false;
+        let mut seen_group = false;
+        let mut fields: Vec<(&str, &str)> = Vec::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_group = line == GROUP_HEADER;
+                seen_group |= in_group;
+                continue;
+            }
+            if !seen_group {
+                return Err(format!(
+                    "line {}: expected {} before any key",
+                    index + 1,
+                    GROUP_HEADER
+                ));
+            }
+            if !in_group {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key=value`", index + 1))?;
+            let key = key.trim();
+            if fields.iter().any(|(k, _)| *k == key) {
+                return Err(format!("line {}: duplicate key `{}`", index + 1, key));
+            }
+            fields.push((key, value.trim()));
+        }
+
+        if !seen_group {
+            return Err(format!("missing {} group", GROUP_HEADER));
+        }
+        let get = |key: &str| fields.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        let require = |key: &str| get(key).ok_or_else(|| format!("missing `{}`", key));
+        let boolean = |key: &str| match get(key) {
+            None | Some("false") => Ok(false),
+            Some("true") => Ok(true),
+            Some(other) => Err(format!("`{}` must be true or false, got `{}`", key, other)),
+        };
+
+        let ty = require("Type")?;
+        if ty != "Application" {
+            return Err(format!("`Type` must be Application, got `{}`", ty));
+        }
+        let name = unescape_value(require("Name")?)?;
+        let mut exec = split_exec(&unescape_value(require("Exec")?)?)?.into_iter();
+        let program = exec.next().ok_or("`Exec` is empty")?;
+        let delay_secs = match get("X-GNOME-Autostart-Delay") {
+            Some(delay) => delay
+                .parse()
+                .map_err(|_| format!("invalid `X-GNOME-Autostart-Delay`: `{}`", delay))?,
+            None => 0,
+        };
+        // 两个字段都可以禁用自动启动
+        let hidden = boolean("Hidden")? || get("X-GNOME-Autostart-enabled") == Some("false");
+        boolean("X-GNOME-Autostart-enabled")?;
+
+        Ok(Self {
+            name,
+            exec: PathBuf::from(program),
+            args: exec.collect(),
+            delay_secs,
+            hidden,
+        })
+    }
+}
+
+/// XDG 自动启动目录中的 `echo.desktop`
+pub struct XdgAutostart {
+    dir: PathBuf,
+}
+
+impl XdgAutostart {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// 使用 `$XDG_CONFIG_HOME/autostart`，未设置时为 `~/.config/autostart`
+    pub fn from_env() -> Option<Self> {
+        Self::from_dirs(
+            std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+            dirs::home_dir(),
+        )
+    }
+
+    /// 根据 `$XDG_CONFIG_HOME` 和主目录确定自动启动目录，相对路径的 `$XDG_CONFIG_HOME` 被忽略
+    pub fn from_dirs(config_home: Option<PathBuf>, home: Option<PathBuf>) -> Option<Self> {
+        let config_home = config_home
+            .filter(|path| path.is_absolute())
+            .or_else(|| home.map(|home| home.join(".config")))?;
+        Some(Self::new(config_home.join("autostart")))
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(DESKTOP_FILE_NAME)
+    }
+
+    /// 读取并校验已写入的文件，不存在时返回 `None`
+    pub fn read(&self) -> io::Result<Option<DesktopEntry>> {
+        match fs::read_to_string(self.path()) {
+            Ok(contents) => DesktopEntry::parse(&contents)
+                .map(Some)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// 写入文件并读回校验
+    pub fn write(&self, entry: &DesktopEntry) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path();
+        let tmp = path.with_extension("desktop.tmp");
+        fs::write(&tmp, entry.render())?;
+        fs::rename(&tmp, &path)?;
+
+        match self.read()? {
+            Some(written) if written == *entry => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "{} does not match what was written",
+                path.display()
+            ))),
+        }
+    }
+
+    /// 写入期望的内容，返回写入前与期望内容不一致的地方（例如程序被移动）
+    pub fn sync(&self, expected: &DesktopEntry) -> io::Result<Vec<Drift>> {
+        let drift = match self.read() {
+            Ok(Some(found)) => found.drift(expected),
+            Ok(None) => Vec::new(),
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                vec![Drift::Invalid(err.to_string())]
+            }
+            Err(err) => return Err(err),
+        };
+        self.write(expected)?;
+        Ok(drift)
+    }
+
+    /// 是否已启用（文件存在、有效且没有被隐藏）
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.read(), Ok(Some(entry)) if !entry.hidden)
+    }
+
+    /// 删除文件
+    pub fn remove(&self) -> io::Result<()> {
+        match fs::remove_file(self.path()) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `Exec` 中需要加引号的字符（Desktop Entry 规范中的保留字符）
+const RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')',
+    '`',
+];
+
+/// 引用 `Exec` 中的一个参数，`%` 写为 `%%`
+fn quote_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains(RESERVED) {
+        return arg;
+    }
+    let mut out = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// 拆分 `Exec` 为参数列表（`quote_arg` 的逆操作）
+fn split_exec(exec: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut has_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            '"' => {
+                has_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+                            _ => return Err("invalid escape in quoted `Exec` argument".into()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated quote in `Exec`".into()),
+                    }
+                }
+            }
+            '%' => match chars.next() {
+                Some('%') => {
+                    has_arg = true;
+                    current.push('%');
+                }
+                code => {
+                    return Err(format!(
+                        "unsupported field code `%{}` in `Exec`",
+                        code.map(String::from).unwrap_or_default()
+                    ))
+                }
+            },
+            c if RESERVED.contains(&c) => {
+                return Err(format!(
+                    "reserved character `{}` must be quoted in `Exec`",
+                    c
+                ))
+            }
+            c => {
+                has_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// 字符串值的转义（规范中的 `\s`、`\n`、`\t`、`\r`、`\\`）
+fn escape_value(value: &str) -> String {
+    let mut out = String::new();
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            // 行首空格会被读取时去掉
+            ' ' if index == 0 => out.push_str("\\s"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape_value(value: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            other => {
+                return Err(format!(
+                    "invalid escape `\\{}`",
+                    other.map(String::from).unwrap_or_default()
+                ))
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config_home(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("echo-xdg-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry() -> DesktopEntry {
+        DesktopEntry {
+            name: "echo".into(),
+            exec: PathBuf::from("/opt/Echo App/echo"),
+            args: vec![
+                "--autostarted".into(),
+                "--profile=work \"home\"".into(),
+                "100%".into(),
+            ],
+            delay_secs: 15,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn writes_to_xdg_config_home() {
+        let config_home = temp_config_home("env");
+        let backend =
+            XdgAutostart::from_dirs(Some(config_home.clone()), Some("/home/nobody".into()))
+                .unwrap();
+
+        assert_eq!(
+            backend.path(),
+            config_home.join("autostart").join(DESKTOP_FILE_NAME)
+        );
+        backend.write(&entry()).unwrap();
+        assert!(backend.is_enabled());
+
+        let contents = fs::read_to_string(backend.path()).unwrap();
+        assert!(contents.contains("X-GNOME-Autostart-Delay=15\n"));
+        assert!(contents.contains("Hidden=false\n"));
+        assert!(contents.contains(r#"Exec="/opt/Echo App/echo" --autostarted"#));
+        fs::remove_dir_all(config_home).unwrap();
+    }
+
+    #[test]
+    fn falls_back_to_home_config() {
+        let home = PathBuf::from("/home/nobody");
+        let expected = home.join(".config/autostart").join(DESKTOP_FILE_NAME);
+        let backend = XdgAutostart::from_dirs(None, Some(home.clone())).unwrap();
+        assert_eq!(backend.path(), expected);
+        // 相对路径的 `$XDG_CONFIG_HOME` 无效
+        let backend = XdgAutostart::from_dirs(Some("config".into()), Some(home)).unwrap();
+        assert_eq!(backend.path(), expected);
+        assert!(XdgAutostart::from_dirs(None, None).is_none());
+    }
+
+    #[test]
+    fn sync_reports_repaired_drift() {
+        let config_home = temp_config_home("sync");
+        let backend = XdgAutostart::new(config_home.join("autostart"));
+        assert_eq!(backend.sync(&entry()).unwrap(), vec![]);
+        assert_eq!(backend.sync(&entry()).unwrap(), vec![]);
+
+        let moved = DesktopEntry {
+            exec: PathBuf::from("/usr/bin/echo-app"),
+            ..entry()
+        };
+        assert_eq!(
+            backend.sync(&moved).unwrap(),
+            vec![Drift::ExecMoved {
+                expected: moved.exec.clone(),
+                found: entry().exec,
+            }]
+        );
+        assert_eq!(backend.read().unwrap(), Some(moved.clone()));
+
+        fs::write(backend.path(), "not a desktop file").unwrap();
+        let drift = backend.sync(&moved).unwrap();
+        assert!(
+            matches!(drift.as_slice(), [Drift::Invalid(_)]),
+            "{:?}",
+            drift
+        );
+        assert_eq!(backend.read().unwrap(), Some(moved));
+        fs::remove_dir_all(config_home).unwrap();
+    }
+
+    #[test]
+    fn round_trips_exec_with_args() {
+        let entry = entry();
+        assert_eq!(DesktopEntry::parse(&entry.render()).unwrap(), entry);
+    }
+
+    #[test]
+    fn hidden_entry_is_disabled() {
+        let config_home = temp_config_home("hidden");
+        let backend = XdgAutostart::new(config_home.join("autostart"));
+        assert!(!backend.is_enabled());
+
+        backend
+            .write(&DesktopEntry {
+                hidden: true,
+                ..entry()
+            })
+            .unwrap();
+        assert!(!backend.is_enabled());
+        assert!(fs::read_to_string(backend.path())
+            .unwrap()
+            .contains("Hidden=true\n"));
+
+        backend.remove().unwrap();
+        assert_eq!(backend.read().unwrap(), None);
+        fs::remove_dir_all(config_home).unwrap();
+    }
+
+    #[test]
+    fn reports_moved_binary() {
+        let found = entry();
+        let expected = DesktopEntry {
+            exec: PathBuf::from("/usr/bin/echo-app"),
+            ..entry()
+        };
+        assert_eq!(
+            found.drift(&expected),
+            vec![Drift::ExecMoved {
+                expected: PathBuf::from("/usr/bin/echo-app"),
+                found: PathBuf::from("/opt/Echo App/echo"),
+            }]
+        );
+        assert!(found.drift(&found).is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let invalid = [
+            "Type=Application\nName=echo\nExec=echo\n",
+            "[Desktop Entry]\nName=echo\nExec=echo\n",
+            "[Desktop Entry]\nType=Link\nName=echo\nExec=echo\n",
+            "[Desktop Entry]\nType=Application\nName=echo\n",
+            "[Desktop Entry]\nType=Application\nName=echo\nExec=echo %f\n",
+            "[Desktop Entry]\nType=Application\nName=echo\nExec=\"echo\n",
+            "[Desktop Entry]\nType=Application\nName=echo\nExec=echo\nHidden=yes\n",
+            "[Desktop Entry]\nType=Application\nName=echo\nExec=echo\nX-GNOME-Autostart-Delay=-1\n",
+            "[Desktop Entry]\nType=Application\nName=echo\nName=echo\nExec=echo\n",
+        ];
+        for contents in invalid {
+            assert!(DesktopEntry::parse(contents).is_err(), "{:?}", contents);
+        }
+    }
+}
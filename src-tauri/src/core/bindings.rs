@@ -0,0 +1,354 @@
+use crate::core::{commands, events};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 前端源码目录
+///
+/// 绑定由测试生成：`UPDATE_BINDINGS=1 cargo test --lib bindings` 写入，
+/// 不带该变量时检查提交的绑定是否过期。应用运行时不会写入源码目录。
+pub const FRONTEND_LIB_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../src/lib");
+
+/// TypeScript 类型生成器
+///
+/// 通过 `schemars` 生成 Rust 类型的 JSON Schema，再转换为 TypeScript 类型。
+/// 命名类型（结构体、枚举）收集为声明，引用处使用类型名。
+pub struct TsBuilder {
+    gen: SchemaGenerator,
+    /// 缩进单位，与目标文件保持一致
+    indent: &'static str,
+}
+
+impl TsBuilder {
+    pub fn new(indent: &'static str) -> Self {
+        Self {
+            gen: SchemaGenerator::default(),
+            indent,
+        }
+    }
+
+    /// 注册类型，返回其 TypeScript 类型表达式
+    pub fn type_of<T: JsonSchema>(&mut self) -> String {
+        self.type_from(|gen| gen.subschema_for::<T>())
+    }
+
+    /// 通过 schema 生成函数注册类型，用于只保存了 `fn(&mut SchemaGenerator) -> Schema` 的场合
+    pub fn type_from(&mut self, schema: impl FnOnce(&mut SchemaGenerator) -> Schema) -> String {
+        let schema = schema(&mut self.gen);
+        ts_type(
+            &serde_json::to_value(schema).unwrap_or(Value::Bool(true)),
+            self.indent,
+        )
+    }
+
+    /// 所有已注册命名类型的声明，按名称排序
+    pub fn declarations(&self) -> String {
+        let mut out = String::new();
+        for (name, schema) in self.gen.definitions() {
+            let schema = serde_json::to_value(schema).unwrap_or(Value::Bool(true));
+            out.push_str(&declaration(name, &schema, self.indent));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// 生成单个命名类型的声明
+fn declaration(name: &str, schema: &Value, unit: &str) -> String {
+    let doc = doc_comment(schema, "");
+    if schema.get("properties").is_some() {
+        format!(
+            "{}export interface {} {}\n",
+            doc,
+            name,
+            object_body(schema, "", unit)
+        )
+    } else {
+        format!("{}export type {} = {};\n", doc, name, ts_type(schema, unit))
+    }
+}
+
+/// 对象类型的 `{ ... }` 部分
+///
+/// 带 `#[serde(default)]` 的字段（schema 中不在 `required` 且有 `default`）生成为可选（`?`），
+/// 前端传入时可以省略；不在 `required` 且不可为 `null` 的字段（没有时省略）同样生成为可选。
+/// 其他字段序列化时总会输出（`Option` 为 `null`），生成为必填。
+fn object_body(schema: &Value, indent: &str, unit: &str) -> String {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return "{}".to_string();
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|keys| keys.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let inner = format!("{}{}", indent, unit);
+    let mut out = String::from("{\n");
+    for (key, property) in properties {
+        let optional = !required.contains(&key.as_str())
+            && (property.get("default").is_some() || !is_nullable(property));
+        out.push_str(&doc_comment(property, &inner));
+        out.push_str(&format!(
+            "{}{}{}: {};\n",
+            inner,
+            property_key(key),
+            if optional { "?" } else { "" },
+            ts_type(property, unit)
+        ));
+    }
+    out.push_str(indent);
+    out.push('}');
+    out
+}
+
+/// schema 是否允许 `null`（schemars 把 `Option<T>` 生成为带 `null` 的类型或 `anyOf`）
+fn is_nullable(schema: &Value) -> bool {
+    let null = Value::String("null".into());
+    match schema.get("type") {
+        Some(Value::String(ty)) => ty == "null",
+        Some(Value::Array(types)) => types.contains(&null),
+        _ => ["anyOf", "oneOf"].iter().any(|key| {
+            schema
+                .get(*key)
+                .and_then(Value::as_array)
+                .is_some_and(|variants| variants.iter().any(is_nullable))
+        }),
+    }
+}
+
+/// JSON Schema 转 TypeScript 类型表达式
+fn ts_type(schema: &Value, unit: &str) -> String {
+    let Some(object) = schema.as_object() else {
+        // `true` 表示任意值
+        return "unknown".to_string();
+    };
+
+    // 无法用 JSON Schema 表示的类型（如 `Channel<T>`）直接给出 TS 类型
+    if let Some(ty) = object.get("tsType").and_then(Value::as_str) {
+        return ty.to_string();
+    }
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or("unknown")
+            .to_string();
+    }
+    if let Some(values) = object.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(|v| v.to_string()));
+    }
+    if let Some(value) = object.get("const") {
+        return value.to_string();
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(variants) = object.get(key).and_then(Value::as_array) {
+            return union(variants.iter().map(|v| ts_type(v, unit)));
+        }
+    }
+    if let Some(all) = object.get("allOf").and_then(Value::as_array) {
+        return all
+            .iter()
+            .map(|v| ts_type(v, unit))
+            .collect::<Vec<_>>()
+            .join(" & ");
+    }
+
+    match object.get("type") {
+        Some(Value::String(ty)) => primitive(ty, schema, unit),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|ty| primitive(ty, schema, unit)),
+        ),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn primitive(ty: &str, schema: &Value, unit: &str) -> String {
+    match ty {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let item = schema
+                .get("items")
+                .map(|items| ts_type(items, unit))
+                .unwrap_or("unknown".into());
+            if item.contains(' ') {
+                format!("({})[]", item)
+            } else {
+                format!("{}[]", item)
+            }
+        }
+        "object" => match schema.get("additionalProperties") {
+            _ if schema.get("properties").is_some() => object_body(schema, "", unit),
+            Some(Value::Bool(false)) | None => "Record<string, never>".to_string(),
+            Some(value) => format!("Record<string, {}>", ts_type(value, unit)),
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+/// 合并为联合类型，去除重复项
+fn union(types: impl Iterator<Item = String>) -> String {
+    let mut seen: Vec<String> = Vec::new();
+    for ty in types {
+        if !seen.contains(&ty) {
+            seen.push(ty);
+        }
+    }
+    seen.join(" | ")
+}
+
+fn property_key(key: &str) -> String {
+    let is_ident = key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if is_ident {
+        key.to_string()
+    } else {
+        format!("{:?}", key)
+    }
+}
+
+fn doc_comment(schema: &Value, indent: &str) -> String {
+    match schema.get("description").and_then(Value::as_str) {
+        Some(description) => format!("{}/** {} */\n", indent, description.replace('\n', " ")),
+        None => String::new(),
+    }
+}
+
+/// 替换源码中由标记包围的生成区域
+///
+/// 区域以 `// <generated:{marker}>` 开始、`// </generated:{marker}>` 结束；
+/// 源码中没有该区域时追加到末尾。
+fn render_generated(source: &str, marker: &str, content: &str) -> String {
+    let start = format!("// <generated:{}>", marker);
+    let end = format!("// </generated:{}>", marker);
+    let block = format!(
+        "{}\n// 由 src-tauri 生成，请勿手动修改\n{}{}",
+        start, content, end
+    );
+
+    match (source.find(&start), source.find(&end)) {
+        (Some(s), Some(e)) if s < e => {
+            format!("{}{}{}", &source[..s], block, &source[e + end.len()..])
+        }
+        _ => format!("{}\n\n{}\n", source.trim_end(), block),
+    }
+}
+
+/// 写入生成区域，内容未变化时不写入。返回是否写入了文件。
+pub fn write_generated(path: &Path, marker: &str, content: &str) -> io::Result<bool> {
+    let source = fs::read_to_string(path)?;
+    let updated = render_generated(&source, marker, content);
+    if updated == source {
+        return Ok(false);
+    }
+    fs::write(path, updated)?;
+    Ok(true)
+}
+
+/// 检查文件中的生成区域是否与当前 Rust 类型一致
+pub fn is_up_to_date(path: &Path, marker: &str, content: &str) -> io::Result<bool> {
+    let source = fs::read_to_string(path)?;
+    Ok(render_generated(&source, marker, content) == source)
+}
+
+/// 所有生成的前端绑定：(`src/lib` 下的文件名, 区域标记, 生成内容)
+fn generated_bindings() -> Vec<(&'static str, &'static str, String)> {
+    vec![
+        ("commands.ts", "commands", commands::typescript_bindings()),
+        ("events.ts", "events", events::typescript_bindings()),
+    ]
+}
+
+/// 更新 `src/lib` 下所有生成的绑定，返回被更新的文件名
+fn export_bindings() -> io::Result<Vec<&'static str>> {
+    let dir = Path::new(FRONTEND_LIB_DIR);
+    let mut updated = Vec::new();
+    for (file, marker, content) in generated_bindings() {
+        if write_generated(&dir.join(file), marker, &content)? {
+            updated.push(file);
+        }
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 提交的绑定必须与 Rust 类型一致；设置 `UPDATE_BINDINGS` 时重新生成
+    #[test]
+    fn bindings_are_up_to_date() {
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            for file in export_bindings().unwrap() {
+                eprintln!("updated src/lib/{}", file);
+            }
+            return;
+        }
+
+        let dir = Path::new(FRONTEND_LIB_DIR);
+        for (file, marker, content) in generated_bindings() {
+            assert!(
+                is_up_to_date(&dir.join(file), marker, &content).unwrap(),
+                "src/lib/{} is stale, run `UPDATE_BINDINGS=1 cargo test --lib bindings` to regenerate it",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn defaulted_fields_are_optional() {
+        // `id` 没有默认值，前端必须传入
+        use crate::ui::tray_menu::TrayMenuItem;
+
+        let mut builder = TsBuilder::new("  ");
+        builder.type_of::<TrayMenuItem>();
+        let declarations = builder.declarations();
+        for field in [
+            "  id: string;",
+            "enabled?: boolean;",
+            "items?: TrayMenuItem[];",
+        ] {
+            assert!(declarations.contains(field), "{}", declarations);
+        }
+
+        // 没有默认值的 `Option` 字段总会输出
+        let mut builder = TsBuilder::new("  ");
+        builder.type_of::<crate::core::crash::CrashReport>();
+        assert!(builder
+            .declarations()
+            .contains("  location: string | null;"));
+    }
+
+    #[test]
+    fn event_payloads_are_imported_from_commands() {
+        let events = events::typescript_bindings();
+        assert!(!events.contains("export interface"), "{}", events);
+        let commands = commands::typescript_bindings();
+        for event in events::event_catalog() {
+            assert!(commands.contains(&format!("export interface {} {{", event.payload)));
+            assert!(events.contains(event.payload));
+        }
+    }
+
+    #[test]
+    fn app_error_lists_every_code() {
+        let bindings = commands::typescript_bindings();
+        assert!(bindings.contains("export interface AppError {"));
+        assert!(bindings.contains("  details?: string;"));
+        for code in crate::core::error::AppError::CODES {
+            assert!(bindings.contains(&format!("\"{}\"", code)), "{}", code);
+        }
+    }
+}
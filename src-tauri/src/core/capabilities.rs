@@ -0,0 +1,162 @@
+use crate::core::error::AppResult;
+use tauri::ipc::CapabilityBuilder;
+use tauri::{AppHandle, Manager};
+
+/// 表示全部命令或事件
+const ALL: &[&str] = &["*"];
+
+/// 窗口能力：按窗口 label 声明可调用的应用命令、可接收的应用事件和 Tauri 权限
+///
+/// 应用命令在 `middleware::with_middleware` 中检查，应用事件由 `EventEmitter` 只发给有权接收的窗口；
+/// Tauri 权限（核心和插件命令）在启动时注册为运行时 capability。
+/// 未声明的窗口不能调用任何应用命令，也收不到任何应用事件。
+pub struct WindowCapability {
+    pub label: &'static str,
+    pub commands: &'static [&'static str],
+    pub events: &'static [&'static str],
+    pub permissions: &'static [&'static str],
+}
+
+pub const CAPABILITIES: &[WindowCapability] = &[
+    // 主窗口的 Tauri 权限由 `capabilities/default.json` 声明
+    WindowCapability {
+        label: "main",
+        commands: ALL,
+        events: ALL,
+        permissions: &[],
+    },
+    // 托盘弹出窗口只读配置，不能修改设置或切换自动启动
+    WindowCapability {
+        label: "popover",
+        commands: &[
+            "greet",
+            "get_settings",
+            "get_log_level",
+            "write_log",
+            "subscribe_events",
+        ],
+        events: &["config-changed"],
+        // 点击透明区域时由页面隐藏窗口，`core:default` 不包含隐藏窗口
+        permissions: &["core:default", "core:window:allow-hide"],
+    },
+    WindowCapability {
+        label: "about",
+        commands: &["get_settings", "write_log"],
+        events: &[],
+        permissions: &["core:default", "opener:default"],
+    },
+    WindowCapability {
+        label: "crash-reports",
+        commands: &["get_crash_reports", "dismiss_crash_reports", "write_log"],
+        events: &[],
+        // 查看后由页面关闭窗口，`core:default` 不包含关闭窗口
+        permissions: &["core:default", "core:window:allow-close"],
+    },
+];
+
+/// 窗口的能力声明
+pub fn capability_for(label: &str) -> Option<&'static WindowCapability> {
+    CAPABILITIES
+        .iter()
+        .find(|capability| capability.label == label)
+}
+
+/// 窗口是否可以调用应用命令
+pub fn allows_command(label: &str, command: &str) -> bool {
+    capability_for(label).is_some_and(|capability| matches(capability.commands, command))
+}
+
+/// 窗口是否可以接收应用事件
+pub fn allows_event(label: &str, event: &str) -> bool {
+    capability_for(label).is_some_and(|capability| matches(capability.events, event))
+}
+
+fn matches(allowed: &[&str], name: &str) -> bool {
+    allowed.iter().any(|item| *item == "*" || *item == name)
+}
+
+/// 将各窗口的 Tauri 权限注册为运行时 capability
+pub fn register_capabilities(app: &AppHandle) -> AppResult<()> {
+    for capability in CAPABILITIES {
+        if capability.permissions.is_empty() {
+            continue;
+        }
+        let builder = capability.permissions.iter().fold(
+            CapabilityBuilder::new(format!("window-{}", capability.label)).window(capability.label),
+            |builder, permission| builder.permission(*permission),
+        );
+        app.add_capability(builder)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::commands::command_catalog;
+    use crate::core::events::event_catalog;
+
+    #[test]
+    fn popover_cannot_call_privileged_commands() {
+        for command in [
+            "set_settings",
+            "set_autostart",
+            "set_log_level",
+            "dismiss_crash_reports",
+            "get_command_metrics",
+        ] {
+            assert!(!allows_command("popover", command), "{}", command);
+        }
+        assert!(allows_command("popover", "get_settings"));
+    }
+
+    #[test]
+    fn popover_cannot_toggle_autostart() {
+        let popover = capability_for("popover").unwrap();
+        assert!(!popover
+            .permissions
+            .iter()
+            .any(|permission| permission.starts_with("autostart:")));
+        assert!(popover.permissions.contains(&"core:window:allow-hide"));
+    }
+
+    #[test]
+    fn autostart_plugin_commands_are_not_exposed() {
+        // 前端通过 `get_autostart` / `set_autostart` 切换，Linux 上没有注册插件
+        let main: serde_json::Value =
+            serde_json::from_str(include_str!("../../capabilities/default.json")).unwrap();
+        let permissions = main["permissions"].as_array().unwrap();
+        assert!(!permissions
+            .iter()
+            .filter_map(|permission| permission.as_str())
+            .any(|permission| permission.starts_with("autostart:")));
+    }
+
+    #[test]
+    fn crash_reports_window_can_close_itself() {
+        let crash_reports = capability_for("crash-reports").unwrap();
+        assert!(crash_reports
+            .permissions
+            .contains(&"core:window:allow-close"));
+    }
+
+    #[test]
+    fn undeclared_windows_are_denied() {
+        assert!(!allows_command("unknown", "get_settings"));
+        assert!(!allows_event("unknown", "config-changed"));
+    }
+
+    #[test]
+    fn capabilities_reference_known_commands_and_events() {
+        let commands: Vec<_> = command_catalog().iter().map(|c| c.name).collect();
+        let events: Vec<_> = event_catalog().iter().map(|e| e.name).collect();
+        for capability in CAPABILITIES {
+            for command in capability.commands.iter().filter(|c| **c != "*") {
+                assert!(commands.contains(command), "unknown command {}", command);
+            }
+            for event in capability.events.iter().filter(|e| **e != "*") {
+                assert!(events.contains(event), "unknown event {}", event);
+            }
+        }
+    }
+}
@@ -1,29 +1,416 @@
 #![allow(unused)]
-use crate::core::events::EventEmitter;
+use crate::config::autostart::{set_autostart_enabled, AutostartStatus};
+use crate::config::setup::AppConfig;
+use crate::config::store::SettingsStore;
+use crate::config::watcher::{notify_changes, register_autostart};
+use crate::core::bindings::TsBuilder;
+use crate::core::crash::{self, CrashReport};
+use crate::core::error::{AppError, AppResult};
+use crate::core::events::{self, EventEmitter, EventMessage};
+use crate::core::logger::{self, LogEntry};
+use crate::core::middleware::{CommandMetrics, CommandStats};
+use crate::ui::tray;
+use crate::ui::tray_animation::{self, TrayAnimationOptions};
+use crate::ui::tray_icon::{TrayState, TrayStatus};
+use crate::ui::tray_menu::{self, TrayMenu, TrayMenuItem};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use serde_json::{json, Map, Value};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, State, WebviewWindow};
 
 // 定义命令参数和返回值的类型
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GreetParams {
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GreetResponse {
     pub message: String,
 }
 
-// 命令实现
-#[tauri::command]
-pub fn greet(emitter: State<EventEmitter>, params: GreetParams) -> Result<GreetResponse, String> {
-    Ok(GreetResponse {
-        message: format!("Hello, {}! You've been greeted from Rust!", params.name),
-    })
+/// 参数或返回值类型的 JSON Schema 生成函数
+pub type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// 命令目录中的一项，用于生成前端绑定和参数校验
+pub struct CommandDescriptor {
+    pub name: &'static str,
+    /// 前端可见的参数（不含 `State`、`AppHandle` 等注入参数）
+    pub args: Vec<CommandArg>,
+    pub ret: SchemaFn,
+    pub doc: String,
+}
+
+/// 命令的前端参数
+pub struct CommandArg {
+    pub name: &'static str,
+    pub schema: SchemaFn,
+}
+
+/// `Channel<T>` 参数的 schema：前端传入 channel 的 id 字符串，TS 中生成为 `Channel<T>`
+fn channel_schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    // 注册消息类型，使其出现在生成的声明中
+    gen.subschema_for::<T>();
+    let mut schema = SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        ..Default::default()
+    };
+    schema.extensions.insert(
+        "tsType".to_string(),
+        Value::String(format!("Channel<{}>", T::schema_name())),
+    );
+    schema.into()
+}
+
+/// 从命令函数的参数列表中取出前端参数
+///
+/// `AppHandle`、`WebviewWindow` 和 `State<..>` 由 Tauri 注入，不出现在前端调用中。
+macro_rules! command_args {
+    ([$($out:tt)*] $arg:ident: AppHandle $(, $($rest:tt)*)?) => {
+        command_args!([$($out)*] $($($rest)*)?)
+    };
+    ([$($out:tt)*] $arg:ident: WebviewWindow $(, $($rest:tt)*)?) => {
+        command_args!([$($out)*] $($($rest)*)?)
+    };
+    ([$($out:tt)*] $arg:ident: Channel<$message:ty> $(, $($rest:tt)*)?) => {
+        command_args!(
+            [$($out)* CommandArg { name: stringify!($arg), schema: channel_schema::<$message> },]
+            $($($rest)*)?
+        )
+    };
+    ([$($out:tt)*] $arg:ident: State<$state:ty> $(, $($rest:tt)*)?) => {
+        command_args!([$($out)*] $($($rest)*)?)
+    };
+    ([$($out:tt)*] $arg:ident: $ty:ty $(, $($rest:tt)*)?) => {
+        command_args!(
+            [$($out)* CommandArg { name: stringify!($arg), schema: |gen| gen.subschema_for::<$ty>() },]
+            $($($rest)*)?
+        )
+    };
+    ([$($out:tt)*]) => {
+        vec![$($out)*]
+    };
+}
+
+/// 定义命令
+///
+/// 每一项是一个完整的命令函数，展开为 `#[tauri::command]` 函数，
+/// 同时生成 `get_command_handlers()` 和 `command_catalog()`。
+/// 目录直接取自函数签名，注册的命令、参数校验和前端绑定不会与实现不一致。
+macro_rules! commands {
+    ($(
+        $(#[doc = $doc:literal])*
+        pub fn $name:ident($($params:tt)*) -> AppResult<$ret:ty> $body:block
+    )*) => {
+        $(
+            $(#[doc = $doc])*
+            #[tauri::command]
+            pub fn $name($($params)*) -> AppResult<$ret> $body
+        )*
+
+        /// 导出所有命令处理器
+        pub fn get_command_handlers(
+        ) -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bool + Send + Sync + Clone + 'static {
+            tauri::generate_handler![$($name),*]
+        }
+
+        /// 所有已注册的命令
+        pub fn command_catalog() -> Vec<CommandDescriptor> {
+            vec![$(CommandDescriptor {
+                name: stringify!($name),
+                args: command_args!([] $($params)*),
+                ret: |gen| gen.subschema_for::<$ret>(),
+                doc: [$($doc),*].map(str::trim).join("\n"),
+            }),*]
+        }
+    };
+}
+
+commands! {
+    /// 问候命令
+    pub fn greet(emitter: State<EventEmitter>, params: GreetParams) -> AppResult<GreetResponse> {
+        Ok(GreetResponse {
+            message: format!("Hello, {}! You've been greeted from Rust!", params.name),
+        })
+    }
+
+    /// 获取持久化配置
+    pub fn get_settings(store: State<SettingsStore>) -> AppResult<AppConfig> {
+        Ok(store.get())
+    }
+
+    /// 更新配置并写回磁盘
+    pub fn set_settings(
+        app: AppHandle,
+        store: State<SettingsStore>,
+        settings: AppConfig,
+    ) -> AppResult<AppConfig> {
+        let old = store.get();
+        store.set(settings)?;
+        let new = store.get();
+        // 自动启动注册失败时恢复原配置，避免配置与系统中的注册状态不一致
+        if let Err(err) = register_autostart(&app, &old, &new) {
+            if let Err(rollback) = store.set(old) {
+                log::error!("failed to roll back settings: {}", rollback);
+            }
+            return Err(err);
+        }
+        notify_changes(&app, &old, &new);
+        Ok(new)
+    }
+
+    /// 获取自动启动设置
+    pub fn get_autostart(store: State<SettingsStore>) -> AppResult<bool> {
+        Ok(store.get().autostart_enabled)
+    }
+
+    /// 开启或关闭自动启动，返回系统中的实际注册状态和修复的差异
+    pub fn set_autostart(app: AppHandle, enabled: bool) -> AppResult<AutostartStatus> {
+        set_autostart_enabled(&app, enabled)
+    }
+
+    /// 获取最近的日志
+    pub fn get_recent_logs(limit: Option<usize>) -> AppResult<Vec<LogEntry>> {
+        // 默认 200 条
+        Ok(logger::recent(limit.unwrap_or(200)))
+    }
+
+    /// 获取当前日志级别
+    pub fn get_log_level() -> AppResult<String> {
+        Ok(logger::level().to_string().to_lowercase())
+    }
+
+    /// 运行时调整日志级别
+    pub fn set_log_level(level: String) -> AppResult<()> {
+        let level = level
+            .parse()
+            .map_err(|_| AppError::InvalidArgument(format!("invalid log level: {}", level)))?;
+        logger::set_level(level);
+        Ok(())
+    }
+
+    /// 将前端日志写入应用日志
+    pub fn write_log(level: String, message: String) -> AppResult<()> {
+        let level = level
+            .parse()
+            .map_err(|_| AppError::InvalidArgument(format!("invalid log level: {}", level)))?;
+        logger::log_frontend(level, &message);
+        Ok(())
+    }
+
+    /// 获取尚未查看的崩溃报告
+    pub fn get_crash_reports() -> AppResult<Vec<CrashReport>> {
+        Ok(crash::pending_reports())
+    }
+
+    /// 将崩溃报告标记为已查看，不传 ids 时处理全部
+    pub fn dismiss_crash_reports(ids: Option<Vec<String>>) -> AppResult<()> {
+        crash::dismiss_reports(&ids.unwrap_or_default())?;
+        Ok(())
+    }
+
+    /// 获取各命令的调用统计
+    pub fn get_command_metrics(stats: State<CommandStats>) -> AppResult<Vec<CommandMetrics>> {
+        Ok(stats.snapshot())
+    }
+
+    /// 获取托盘菜单
+    pub fn get_tray_menu(menu: State<TrayMenu>) -> AppResult<Vec<TrayMenuItem>> {
+        Ok(menu.items())
+    }
+
+    /// 注册托盘菜单项，`parent` 为子菜单 id，`before` 为插入位置（默认在“退出”之前）
+    pub fn add_tray_menu_item(
+        app: AppHandle,
+        item: TrayMenuItem,
+        parent: Option<String>,
+        before: Option<String>,
+    ) -> AppResult<()> {
+        tray_menu::add_item(&app, item, parent.as_deref(), before.as_deref())
+    }
+
+    /// 删除托盘菜单项（内置菜单项不能删除）
+    pub fn remove_tray_menu_item(app: AppHandle, id: String) -> AppResult<()> {
+        tray_menu::remove_item(&app, &id)
+    }
+
+    /// 修改托盘菜单项的文字、可用和勾选状态，未传入的字段保持不变
+    pub fn update_tray_menu_item(
+        app: AppHandle,
+        id: String,
+        label: Option<String>,
+        enabled: Option<bool>,
+        checked: Option<bool>,
+    ) -> AppResult<TrayMenuItem> {
+        tray_menu::update_item(&app, &id, label, enabled, checked)
+    }
+
+    /// 切换托盘图标状态，`count` 为未读数（仅 `unread`）
+    pub fn set_tray_status(app: AppHandle, state: TrayState, count: Option<u32>) -> AppResult<()> {
+        tray::set_tray_status(&app, TrayStatus::new(state, count.unwrap_or(0)))
+    }
+
+    /// 开始播放托盘图标动画（GIF、APNG、精灵图或内置加载动画），替换正在播放的动画
+    pub fn start_tray_animation(app: AppHandle, options: TrayAnimationOptions) -> AppResult<()> {
+        tray_animation::start_animation(&app, &options)
+    }
+
+    /// 停止托盘图标动画并恢复为当前状态的图标
+    pub fn stop_tray_animation(app: AppHandle) -> AppResult<()> {
+        tray_animation::stop_animation(&app)
+    }
+
+    /// 订阅应用事件，只会收到当前窗口有权接收的事件（由 `eventManager` 调用）
+    pub fn subscribe_events(
+        window: WebviewWindow,
+        emitter: State<EventEmitter>,
+        channel: Channel<EventMessage>,
+    ) -> AppResult<()> {
+        emitter.subscribe(window.label(), channel);
+        Ok(())
+    }
 }
 
-// 导出所有命令处理器
-pub fn get_command_handlers(
-) -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bool + Send + Sync + Clone + 'static {
-    tauri::generate_handler![greet]
+/// 每个命令参数的 JSON Schema，用于调用前校验
+///
+/// 返回的 schema 是一个对象，属性为 camelCase 参数名，与前端 `invoke` 传入的参数一致。
+pub fn command_schemas() -> Vec<(&'static str, Value)> {
+    let mut gen = SchemaGenerator::default();
+    let commands: Vec<(&'static str, Map<String, Value>)> = command_catalog()
+        .into_iter()
+        .map(|command| {
+            let properties = command
+                .args
+                .iter()
+                .map(|arg| {
+                    let schema = (arg.schema)(&mut gen);
+                    (
+                        camel_case(arg.name),
+                        serde_json::to_value(schema).unwrap_or_default(),
+                    )
+                })
+                .collect();
+            (command.name, properties)
+        })
+        .collect();
+    let definitions = serde_json::to_value(gen.definitions()).unwrap_or_default();
+    commands
+        .into_iter()
+        .map(|(name, properties)| {
+            let schema = json!({
+                "type": "object",
+                "properties": properties,
+                "definitions": definitions,
+            });
+            (name, schema)
+        })
+        .collect()
+}
+
+/// 生成前端命令客户端：参数/返回值类型和 `Commands` 类
+///
+/// 参数名按 Tauri 的约定转换为 camelCase；`Option` 参数生成为可选参数，
+/// 返回 `()` 的命令生成为 `Promise<void>`。
+pub fn typescript_bindings() -> String {
+    let mut builder = TsBuilder::new("  ");
+    // 命令失败时 `invoke` 抛出的错误
+    builder.type_of::<AppError>();
+    // 事件负载类型也声明在这里，`events.ts` 从这里导入
+    events::event_types(&mut builder);
+    let commands: Vec<(CommandDescriptor, Vec<String>, String)> = command_catalog()
+        .into_iter()
+        .map(|command| {
+            let args = command
+                .args
+                .iter()
+                .map(|arg| builder.type_from(arg.schema))
+                .collect();
+            let ret = builder.type_from(command.ret);
+            (command, args, ret)
+        })
+        .collect();
+
+    let mut class = String::from("// 命令调用封装类\nexport class Commands {\n");
+    let mut shortcuts = String::from("// 导出便捷方法\n");
+    for (index, (command, arg_types, ret)) in commands.iter().enumerate() {
+        let method = camel_case(command.name);
+        let ret = if ret == "null" { "void" } else { ret.as_str() };
+        let params: Vec<String> = command
+            .args
+            .iter()
+            .zip(arg_types)
+            .map(|(arg, ty)| match ty.strip_suffix(" | null") {
+                Some(ty) => format!("{}?: {}", camel_case(arg.name), ty),
+                None => format!("{}: {}", camel_case(arg.name), ty),
+            })
+            .collect();
+        let invoke_args = if command.args.is_empty() {
+            String::new()
+        } else {
+            let keys: Vec<String> = command
+                .args
+                .iter()
+                .map(|arg| camel_case(arg.name))
+                .collect();
+            format!(", {{ {} }}", keys.join(", "))
+        };
+
+        if index > 0 {
+            class.push('\n');
+        }
+        class.push_str(&format!(
+            "  /**\n   * {}\n   */\n  \
+             static async {}({}): Promise<{}> {{\n    \
+             return await invoke<{}>(\"{}\"{});\n  }}\n",
+            command.doc.replace('\n', "\n   * "),
+            method,
+            params.join(", "),
+            ret,
+            ret,
+            command.name,
+            invoke_args
+        ));
+        shortcuts.push_str(&format!("export const {} = Commands.{};\n", method, method));
+    }
+    class.push_str("}\n\n");
+
+    format!("{}{}{}", builder.declarations(), class, shortcuts)
+}
+
+/// snake_case 转 camelCase（Tauri 命令参数的默认命名方式）
+pub(crate) fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_args_exclude_injected_params() {
+        let args = |name: &str| -> Vec<&'static str> {
+            let catalog = command_catalog();
+            let command = catalog.iter().find(|c| c.name == name).unwrap();
+            command.args.iter().map(|arg| arg.name).collect()
+        };
+        assert_eq!(args("greet"), ["params"]);
+        assert_eq!(args("set_settings"), ["settings"]);
+        assert_eq!(args("add_tray_menu_item"), ["item", "parent", "before"]);
+        assert!(args("stop_tray_animation").is_empty());
+    }
 }
@@ -0,0 +1,125 @@
+use crate::config::cli::CliArgs;
+use crate::config::store::SettingsStore;
+use crate::core::instance::{self, InstanceMessage, InstanceReply};
+use crate::ui::menu::{handle_close_event, handle_open_event};
+use crate::ui::tray::{handle_tray_menu_event, toggle_popover};
+use serde::{Deserialize, Serialize};
+use tauri::menu::MenuEvent;
+use tauri::{AppHandle, Manager};
+
+/// 远程控制子命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlCommand {
+    Show,
+    Hide,
+    PopoverToggle,
+    PopoverShow,
+    PopoverHide,
+    Quit,
+    Status,
+}
+
+/// 运行中实例的状态（`echo status` 的输出）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceStatus {
+    pub version: String,
+    pub pid: u32,
+    pub main_visible: bool,
+    pub popover_visible: bool,
+    pub autostart_enabled: bool,
+    pub settings_path: String,
+}
+
+/// 在主实例中执行控制命令
+///
+/// 命令映射到托盘和菜单使用的同一组处理函数。
+/// `Quit` 在回复发送之后由调用方处理，避免客户端收不到回复。
+pub fn handle_control(app: &AppHandle, command: ControlCommand) -> InstanceReply {
+    match command {
+        ControlCommand::Show => handle_tray_menu_event(app, MenuEvent { id: "main".into() }),
+        ControlCommand::Hide => handle_close_event(app),
+        ControlCommand::PopoverToggle => set_popover(app, None),
+        ControlCommand::PopoverShow => set_popover(app, Some(true)),
+        ControlCommand::PopoverHide => set_popover(app, Some(false)),
+        ControlCommand::Quit => {}
+        ControlCommand::Status => {
+            return InstanceReply {
+                status: Some(status(app)),
+                ..InstanceReply::ok()
+            }
+        }
+    }
+    InstanceReply::ok()
+}
+
+/// 切换或设置 popover 的可见性
+fn set_popover(app: &AppHandle, visible: Option<bool>) {
+    let Some(popover) = app.get_webview_window("popover") else {
+        return;
+    };
+    let is_visible = popover.is_visible().unwrap_or(false);
+    if visible.is_none_or(|visible| visible != is_visible) {
+        toggle_popover(app, None);
+    }
+}
+
+fn status(app: &AppHandle) -> InstanceStatus {
+    let is_visible = |label: &str| {
+        app.get_webview_window(label)
+            .and_then(|window| window.is_visible().ok())
+            .unwrap_or(false)
+    };
+    let store = app.state::<SettingsStore>();
+
+    InstanceStatus {
+        version: app.package_info().version.to_string(),
+        pid: std::process::id(),
+        main_visible: is_visible("main"),
+        popover_visible: is_visible("popover"),
+        autostart_enabled: store.get().autostart_enabled,
+        settings_path: store.path().display().to_string(),
+    }
+}
+
+/// 客户端模式：把子命令发送给运行中的实例，返回进程退出码
+pub fn run_client(cli: &CliArgs, command: ControlCommand) -> i32 {
+    let message = InstanceMessage::Control { command };
+    let reply = match instance::send(cli.profile.as_deref(), &message) {
+        Ok(reply) => reply,
+        Err(err) => {
+            eprintln!("echo is not running: {}", err);
+            return 1;
+        }
+    };
+
+    if !reply.ok {
+        eprintln!("error: {}", reply.error.unwrap_or_default());
+        return 1;
+    }
+
+    if let Some(status) = reply.status {
+        if cli.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&status).unwrap_or_default()
+            );
+        } else {
+            println!("version:           {}", status.version);
+            println!("pid:               {}", status.pid);
+            println!("main window:       {}", visibility(status.main_visible));
+            println!("popover:           {}", visibility(status.popover_visible));
+            println!("autostart enabled: {}", status.autostart_enabled);
+            println!("settings:          {}", status.settings_path);
+        }
+    }
+    0
+}
+
+fn visibility(visible: bool) -> &'static str {
+    if visible {
+        "visible"
+    } else {
+        "hidden"
+    }
+}
@@ -0,0 +1,143 @@
+use crate::core::logger;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::fs;
+use std::io;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 崩溃报告目录名（位于应用数据目录下）
+const CRASH_DIR_NAME: &str = "crashes";
+
+/// 已查看的崩溃报告移动到此子目录
+const SEEN_DIR_NAME: &str = "seen";
+
+/// 报告中附带的最近日志条数
+const RECENT_LOG_LINES: usize = 100;
+
+/// 崩溃报告
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CrashReport {
+    pub id: String,
+    pub timestamp: String,
+    pub version: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub recent_logs: Vec<String>,
+}
+
+static CRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 崩溃报告目录，与 `PathResolver::app_data_dir` 下的 `crashes` 目录相同
+pub fn resolve_crash_dir(identifier: &str) -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(identifier).join(CRASH_DIR_NAME))
+}
+
+/// 安装 panic 钩子
+///
+/// release 构建使用 `panic = "abort"`，进程会在钩子返回后立即终止，
+/// 因此报告必须在钩子内同步写入磁盘。
+pub fn install_panic_hook(dir: PathBuf, version: &'static str) {
+    if CRASH_DIR.set(dir).is_err() {
+        return;
+    }
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if let Some(dir) = CRASH_DIR.get() {
+            let report = build_report(info, version);
+            if let Err(err) = write_report(dir, &report) {
+                eprintln!("failed to write crash report: {}", err);
+            }
+        }
+        previous(info);
+    }));
+}
+
+fn build_report(info: &PanicHookInfo, version: &str) -> CrashReport {
+    let now = chrono::Local::now();
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string());
+
+    CrashReport {
+        id: format!("crash-{}", now.format("%Y%m%d-%H%M%S%.3f")),
+        timestamp: now.to_rfc3339(),
+        version: version.to_string(),
+        message,
+        location: info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        thread: std::thread::current().name().map(str::to_string),
+        backtrace: Backtrace::force_capture().to_string(),
+        // 不能在钩子中等待日志锁
+        recent_logs: logger::try_recent(RECENT_LOG_LINES)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| {
+                format!(
+                    "{} [{}] {}: {}",
+                    entry.timestamp, entry.level, entry.target, entry.message
+                )
+            })
+            .collect(),
+    }
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let json = serde_json::to_string_pretty(report).map_err(io::Error::other)?;
+    fs::write(dir.join(format!("{}.json", report.id)), json)
+}
+
+/// 尚未查看的崩溃报告，按时间顺序排列
+pub fn pending_reports() -> Vec<CrashReport> {
+    let Some(dir) = CRASH_DIR.get() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut reports: Vec<CrashReport> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let contents = fs::read_to_string(&path).ok()?;
+            match serde_json::from_str(&contents) {
+                Ok(report) => Some(report),
+                Err(err) => {
+                    log::warn!("invalid crash report {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect();
+    reports.sort_by(|a: &CrashReport, b| a.id.cmp(&b.id));
+    reports
+}
+
+/// 将崩溃报告标记为已查看（移动到 `seen` 子目录），`ids` 为空时处理全部
+pub fn dismiss_reports(ids: &[String]) -> io::Result<()> {
+    let Some(dir) = CRASH_DIR.get() else {
+        return Ok(());
+    };
+    let seen = dir.join(SEEN_DIR_NAME);
+    fs::create_dir_all(&seen)?;
+
+    for report in pending_reports() {
+        if ids.is_empty() || ids.contains(&report.id) {
+            let file = format!("{}.json", report.id);
+            fs::rename(dir.join(&file), seen.join(&file))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// 应用统一错误类型
+///
+/// 序列化给前端时是一个结构化对象：
+/// ```json
+/// { "code": "IO_ERROR", "message": "...", "details": "NotFound" }
+/// ```
+/// `code` 是稳定的错误码，前端可以据此区分错误类型。
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0}")]
+    Tauri(#[from] tauri::Error),
+
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Autostart(#[from] tauri_plugin_autostart::Error),
+
+    #[error("{0}")]
+    Serialize(#[from] serde_json::Error),
+
+    /// 配置读取或写入失败
+    #[error("{0}")]
+    Config(String),
+
+    /// 命令参数无效
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    /// 当前窗口无权调用该命令
+    #[error("{0}")]
+    PermissionDenied(String),
+
+    /// 插件被配置关闭
+    #[error("plugin `{0}` is disabled")]
+    PluginDisabled(&'static str),
+
+    /// 找不到窗口、资源等
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+/// 应用统一结果类型
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// 所有错误码，用于生成前端类型
+    pub const CODES: &'static [&'static str] = &[
+        "TAURI_ERROR",
+        "IO_ERROR",
+        "AUTOSTART_ERROR",
+        "SERIALIZE_ERROR",
+        "CONFIG_ERROR",
+        "INVALID_ARGUMENT",
+        "PERMISSION_DENIED",
+        "PLUGIN_DISABLED",
+        "NOT_FOUND",
+        "INTERNAL_ERROR",
+    ];
+
+    /// 稳定的错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Tauri(_) => "TAURI_ERROR",
+            AppError::Io(_) => "IO_ERROR",
+            AppError::Autostart(_) => "AUTOSTART_ERROR",
+            AppError::Serialize(_) => "SERIALIZE_ERROR",
+            AppError::Config(_) => "CONFIG_ERROR",
+            AppError::InvalidArgument(_) => "INVALID_ARGUMENT",
+            AppError::PermissionDenied(_) => "PERMISSION_DENIED",
+            AppError::PluginDisabled(_) => "PLUGIN_DISABLED",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// 附加的错误细节
+    pub fn details(&self) -> Option<String> {
+        match self {
+            AppError::Tauri(err) => Some(format!("{:?}", err)),
+            AppError::Io(err) => Some(format!("{:?}", err.kind())),
+            AppError::Serialize(err) => {
+                Some(format!("line {}, column {}", err.line(), err.column()))
+            }
+            AppError::PluginDisabled(name) => Some(name.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal(message.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let details = self.details();
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(details) = details {
+            state.serialize_field("details", &details)?;
+        } else {
+            state.skip_field("details")?;
+        }
+        state.end()
+    }
+}
+
+/// 与 `Serialize` 的输出一致，`details` 没有时省略
+impl JsonSchema for AppError {
+    fn schema_name() -> String {
+        "AppError".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        serde_json::from_value(serde_json::json!({
+            "description": "命令失败时返回的结构化错误",
+            "type": "object",
+            "required": ["code", "message"],
+            "properties": {
+                "code": { "type": "string", "enum": AppError::CODES },
+                "message": { "type": "string" },
+                "details": { "type": "string" }
+            }
+        }))
+        .expect("AppError schema is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_cover_every_variant() {
+        let errors = [
+            AppError::Tauri(tauri::Error::WindowNotFound),
+            AppError::Io(std::io::Error::other("io")),
+            AppError::Autostart(tauri_plugin_autostart::Error::Anyhow("autostart".into())),
+            AppError::Serialize(serde_json::from_str::<u32>("x").unwrap_err()),
+            AppError::Config("config".into()),
+            AppError::InvalidArgument("argument".into()),
+            AppError::PermissionDenied("denied".into()),
+            AppError::PluginDisabled("opener"),
+            AppError::NotFound("missing".into()),
+            AppError::Internal("internal".into()),
+        ];
+        let codes: Vec<&str> = errors.iter().map(AppError::code).collect();
+        assert_eq!(codes, AppError::CODES);
+    }
+}
@@ -1,26 +1,270 @@
+use crate::config::setup::AppConfig;
+use crate::core::bindings::TsBuilder;
+use crate::core::capabilities;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::ipc::Channel;
+
+/// 应用事件：每个负载类型对应一个固定的事件名
+///
+/// 通过 `event_catalog!` 声明，不要手动实现。
+pub trait AppEvent: Serialize + Clone + JsonSchema {
+    /// 事件名（前端 `eventManager.on` 使用）
+    const NAME: &'static str;
+}
+
+/// 事件目录中的一项，用于生成前端绑定
+pub struct EventDescriptor {
+    pub name: &'static str,
+    pub payload: &'static str,
+    pub listener: &'static str,
+}
+
+/// 声明事件目录
+///
+/// 每一项依次为：事件名、负载类型、`EventEmitter` 上的发送方法、前端监听函数名。
+/// 展开后为负载类型实现 `AppEvent`，生成 `emit_*` 方法和 `event_catalog()`。
+macro_rules! event_catalog {
+    ($($(#[$doc:meta])* $name:literal => $payload:ident, $emit:ident, $listener:ident;)*) => {
+        $(
+            impl AppEvent for $payload {
+                const NAME: &'static str = $name;
+            }
+        )*
+
+        impl EventEmitter {
+            $(
+                $(#[$doc])*
+                pub fn $emit(&self, payload: $payload) -> Result<(), tauri::Error> {
+                    self.emit(payload)
+                }
+            )*
+        }
+
+        /// 所有已声明的事件
+        pub fn event_catalog() -> Vec<EventDescriptor> {
+            vec![$(EventDescriptor {
+                name: $name,
+                payload: stringify!($payload),
+                listener: stringify!($listener),
+            }),*]
+        }
+
+        /// 注册所有事件负载类型，返回 (事件描述, 负载的 TS 类型)
+        pub(crate) fn event_types(builder: &mut TsBuilder) -> Vec<(EventDescriptor, String)> {
+            event_catalog()
+                .into_iter()
+                .zip([$(builder.type_of::<$payload>()),*])
+                .collect()
+        }
+    };
+}
+
+event_catalog! {
+    /// 配置变更（文件被修改或前端调用 `set_settings`）
+    "config-changed" => ConfigChangedPayload, emit_config_changed, onConfigChanged;
+    /// 第二个实例启动，携带其命令行参数
+    "second-instance" => SecondInstancePayload, emit_second_instance, onSecondInstance;
+    /// 托盘菜单项被点击
+    "tray-menu-clicked" => TrayMenuClickedPayload, emit_tray_menu_clicked, onTrayMenuClicked;
+}
+
+/// 配置变更事件负载
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigChangedPayload {
+    /// 变更后的完整配置
+    pub config: AppConfig,
+    /// 发生变化的顶层字段名
+    pub changed: Vec<String>,
+}
+
+/// 第二个实例启动事件负载
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SecondInstancePayload {
+    /// 第二个实例的命令行参数（不包含程序名）
+    pub args: Vec<String>,
+    /// 第二个实例的工作目录
+    pub cwd: String,
+}
+
+/// 托盘菜单点击事件负载
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TrayMenuClickedPayload {
+    /// 菜单项 id
+    pub id: String,
+    /// 勾选项点击后的勾选状态，其他菜单项为 `null`
+    pub checked: Option<bool>,
+}
+
+/// 通过 channel 发送给前端的应用事件
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EventMessage {
+    /// 事件名
+    pub event: String,
+    /// 事件负载
+    pub payload: Value,
+}
 
 /// 使用方法示例
-/// ```
+/// ```ignore
 /// let emitter = app.state::<EventEmitter>();
-/// emitter.emit_user_updated(123, "John Doe".to_string()).unwrap();
-/// emitter.emit_data_changed("user_info".to_string(), 1715548800).unwrap();
-/// emitter.emit("status-changed", "online".to_string()).unwrap();
+/// emitter.emit_config_changed(ConfigChangedPayload { config, changed }).unwrap();
+/// emitter.emit(SecondInstancePayload { args, cwd }).unwrap();
 /// ```
-
 // 事件发送器封装
+//
+// 应用事件不经过 Tauri 的全局事件总线：总线上 `Any` 目标的监听器在所有窗口都会收到事件，
+// 无法按窗口限制。各窗口通过 `subscribe_events` 注册自己的 channel，事件只发给有权接收的窗口。
+#[derive(Default)]
 pub struct EventEmitter {
-    app: AppHandle,
+    /// 各窗口订阅事件的 channel，按窗口 label 保存
+    channels: Mutex<HashMap<String, Channel<EventMessage>>>,
 }
 
 impl EventEmitter {
-    pub fn new(app: AppHandle) -> Self {
-        Self { app }
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 注册窗口的事件 channel，页面重新加载后替换旧的 channel
+    pub fn subscribe(&self, label: &str, channel: Channel<EventMessage>) {
+        self.channels
+            .lock()
+            .unwrap()
+            .insert(label.to_string(), channel);
+    }
+
+    // 通用事件发送方法，事件名由负载类型决定；只发送给有权接收该事件的窗口
+    pub fn emit<E: AppEvent>(&self, payload: E) -> Result<(), tauri::Error> {
+        let message = EventMessage {
+            event: E::NAME.to_string(),
+            payload: serde_json::to_value(payload)?,
+        };
+        // 窗口关闭后 channel 无法再发送，移除
+        self.channels.lock().unwrap().retain(|label, channel| {
+            if !capabilities::allows_event(label, E::NAME) {
+                return true;
+            }
+            match channel.send(message.clone()) {
+                Ok(()) => true,
+                Err(err) => {
+                    log::warn!("dropping event channel of `{}`: {}", label, err);
+                    false
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// 生成前端事件绑定：事件名常量和 `eventManager.on` 封装
+///
+/// 负载类型与命令的类型一起声明在 `commands.ts`，这里只导入，避免同一类型声明两份。
+pub fn typescript_bindings() -> String {
+    let mut builder = TsBuilder::new("    ");
+    let events = event_types(&mut builder);
+
+    let mut payloads: Vec<&str> = events.iter().map(|(_, payload)| payload.as_str()).collect();
+    payloads.sort_unstable();
+    payloads.dedup();
+    let mut out = format!(
+        "import type {{ {} }} from \"./commands\";\n\n",
+        payloads.join(", ")
+    );
+    for (event, payload) in &events {
+        let constant = format!("{}_EVENT", event.name.replace('-', "_").to_uppercase());
+        out.push_str(&format!(
+            "export const {} = \"{}\";\n\n\
+             export const {} = (callback: EventCallback<{}>): Promise<UnlistenFn> =>\n    \
+             eventManager.on<{}>({}, callback);\n\n",
+            constant, event.name, event.listener, payload, payload, constant
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tauri::ipc::InvokeResponseBody;
+
+    /// 为每个窗口注册 channel，记录收到的 (窗口, 事件名)
+    fn subscribe_all(emitter: &EventEmitter, labels: &[&str]) -> Arc<Mutex<Vec<(String, String)>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        for label in labels {
+            let (label, received) = (label.to_string(), received.clone());
+            emitter.subscribe(
+                &label.clone(),
+                Channel::new(move |body| {
+                    let InvokeResponseBody::Json(json) = body else {
+                        panic!("unexpected raw body");
+                    };
+                    let message: EventMessage = serde_json::from_str(&json)?;
+                    received
+                        .lock()
+                        .unwrap()
+                        .push((label.clone(), message.event));
+                    Ok(())
+                }),
+            );
+        }
+        received
+    }
+
+    #[test]
+    fn events_only_reach_allowed_windows() {
+        let emitter = EventEmitter::new();
+        let received = subscribe_all(&emitter, &["main", "popover", "about", "unknown"]);
+
+        emitter
+            .emit_second_instance(SecondInstancePayload {
+                args: vec!["--hidden".into()],
+                cwd: "/tmp".into(),
+            })
+            .unwrap();
+        emitter
+            .emit_tray_menu_clicked(TrayMenuClickedPayload {
+                id: "quit".into(),
+                checked: None,
+            })
+            .unwrap();
+        emitter
+            .emit_config_changed(ConfigChangedPayload {
+                config: AppConfig::default(),
+                changed: vec![],
+            })
+            .unwrap();
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        let expected = [
+            ("main", "config-changed"),
+            ("main", "second-instance"),
+            ("main", "tray-menu-clicked"),
+            ("popover", "config-changed"),
+        ];
+        assert_eq!(
+            received,
+            expected.map(|(label, event)| (label.to_string(), event.to_string()))
+        );
     }
 
-    // 通用事件发送方法
-    pub fn emit<T: Serialize + Clone>(&self, event: &str, payload: T) -> Result<(), tauri::Error> {
-        self.app.emit(event, payload)
+    #[test]
+    fn resubscribing_replaces_the_channel() {
+        let emitter = EventEmitter::new();
+        let first = subscribe_all(&emitter, &["main"]);
+        let second = subscribe_all(&emitter, &["main"]);
+        emitter
+            .emit_config_changed(ConfigChangedPayload {
+                config: AppConfig::default(),
+                changed: vec![],
+            })
+            .unwrap();
+        assert!(first.lock().unwrap().is_empty());
+        assert_eq!(second.lock().unwrap().len(), 1);
     }
 }
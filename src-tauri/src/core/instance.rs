@@ -0,0 +1,434 @@
+use crate::core::control::{handle_control, ControlCommand, InstanceStatus};
+use crate::core::error::AppResult;
+use crate::core::events::{EventEmitter, SecondInstancePayload};
+use crate::ui::menu::handle_open_event;
+use crate::ui::tray::handle_tray_menu_event;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::menu::MenuEvent;
+use tauri::{AppHandle, Manager};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as Listener, UnixStream as Stream};
+
+#[cfg(not(unix))]
+use std::net::{TcpListener as Listener, TcpStream as Stream};
+
+/// 与主实例通信的超时时间
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 转发参数失败时的重试次数和间隔：主实例可能已持有锁但尚未开始监听
+const FORWARD_RETRIES: u32 = 10;
+const FORWARD_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// 实例之间传递的消息（每条消息占一行 JSON）
+///
+/// 每个连接先发送一行访问令牌，再发送一条消息。令牌由主实例生成，
+/// 写在只有当前用户可读的运行时目录中，其他用户或进程无法伪造控制命令。
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InstanceMessage {
+    /// 第二个实例启动时转发的命令行参数
+    Activate { args: Vec<String>, cwd: String },
+    /// 远程控制子命令（`echo show`、`echo status` 等）
+    Control { command: ControlCommand },
+}
+
+/// 主实例对消息的回复
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceReply {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<InstanceStatus>,
+}
+
+impl InstanceReply {
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Self::default()
+        }
+    }
+}
+
+/// 单实例锁
+///
+/// 持有运行时目录下锁文件的独占锁，并监听本地套接字接收其他实例的消息。
+/// 进程退出时锁自动释放。
+pub struct InstanceGuard {
+    _lock: File,
+    token: String,
+    listener: Mutex<Option<Listener>>,
+}
+
+/// 获取单实例锁的结果
+pub enum Acquire {
+    /// 当前进程成为主实例
+    Primary(InstanceGuard),
+    /// 已有主实例在运行，参数已转发
+    Forwarded,
+    /// 已有主实例在运行，未转发参数
+    AlreadyRunning,
+}
+
+/// 实例名称：不同的配置档案可以同时运行
+fn instance_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("echo-{}", profile),
+        None => "echo".to_string(),
+    }
+}
+
+/// 运行时目录：优先使用 `XDG_RUNTIME_DIR`，否则使用按用户区分的临时目录
+fn runtime_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return PathBuf::from(dir);
+    }
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!("com.xiyuer.echo-{}", user))
+}
+
+/// 创建仅当前用户可访问的目录，套接字的访问权限依赖于此
+fn create_private_dir(dir: &std::path::Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// 尝试成为主实例
+///
+/// 获取锁成功时绑定本地套接字并返回 `Primary`；锁已被占用时把 `args`
+/// 转发给主实例并返回 `Forwarded`，`args` 为 `None` 时不转发，返回 `AlreadyRunning`。
+/// 转发重试后仍失败、或无法加锁（权限、文件系统不支持等）时返回错误，
+/// 调用方不应在没有锁的情况下继续运行。
+pub fn acquire(profile: Option<&str>, args: Option<Vec<String>>) -> AppResult<Acquire> {
+    let dir = runtime_dir();
+    create_private_dir(&dir)?;
+    let name = instance_name(profile);
+
+    let lock_path = dir.join(format!("{}.lock", name));
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+
+    let locked = is_locked(lock.try_lock()).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("failed to lock {}: {}", lock_path.display(), err),
+        )
+    })?;
+    if !locked {
+        let Some(args) = args else {
+            return Ok(Acquire::AlreadyRunning);
+        };
+        let cwd = std::env::current_dir()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        forward(profile, &InstanceMessage::Activate { args, cwd })?;
+        return Ok(Acquire::Forwarded);
+    }
+
+    // 先写入令牌再监听，客户端能连接时一定能读到当前实例的令牌
+    let token = generate_token()?;
+    write_private(&dir.join(format!("{}.token", name)), &token)?;
+    let listener = bind(&dir, &name)?;
+    Ok(Acquire::Primary(InstanceGuard {
+        _lock: lock,
+        token,
+        listener: Mutex::new(Some(listener)),
+    }))
+}
+
+/// 是否获得了锁
+///
+/// 只有 `WouldBlock` 表示锁被其他实例持有；其他错误原样返回，不能当作已有实例在运行。
+fn is_locked(result: Result<(), TryLockError>) -> io::Result<bool> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(TryLockError::WouldBlock) => Ok(false),
+        Err(TryLockError::Error(err)) => Err(err),
+    }
+}
+
+/// 把消息转发给主实例，连接失败时重试
+fn forward(profile: Option<&str>, message: &InstanceMessage) -> io::Result<()> {
+    let mut attempt = 0;
+    let reply = loop {
+        match send(profile, message) {
+            Ok(reply) => break reply,
+            Err(err) if attempt < FORWARD_RETRIES => {
+                attempt += 1;
+                log::debug!("forwarding to primary instance failed, retrying: {}", err);
+                thread::sleep(FORWARD_RETRY_DELAY);
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    if reply.ok {
+        Ok(())
+    } else {
+        Err(io::Error::other(reply.error.unwrap_or_default()))
+    }
+}
+
+/// 向主实例发送一条消息并等待回复
+pub fn send(profile: Option<&str>, message: &InstanceMessage) -> io::Result<InstanceReply> {
+    let dir = runtime_dir();
+    let name = instance_name(profile);
+    let token = fs::read_to_string(dir.join(format!("{}.token", name)))?;
+    let mut stream = connect(&dir, &name)?;
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    stream.set_write_timeout(Some(IPC_TIMEOUT))?;
+    stream.write_all(&encode_request(token.trim(), message)?)?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    serde_json::from_str(&reply).map_err(io::Error::other)
+}
+
+/// 生成 256 位随机访问令牌（十六进制）
+fn generate_token() -> io::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|err| io::Error::other(err.to_string()))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// 写入仅当前用户可读写的文件
+fn write_private(path: &std::path::Path, contents: &str) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.create(true).truncate(true).write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// 编码一个请求：令牌一行，消息一行
+fn encode_request(token: &str, message: &InstanceMessage) -> io::Result<Vec<u8>> {
+    let message = serde_json::to_string(message).map_err(io::Error::other)?;
+    Ok(format!("{}\n{}\n", token, message).into_bytes())
+}
+
+/// 读取一个请求，令牌不匹配时不解析消息
+fn read_request(reader: &mut impl BufRead, token: &str) -> Result<InstanceMessage, String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|err| err.to_string())?;
+    if !token_matches(line.trim_end(), token) {
+        return Err("invalid instance token".into());
+    }
+
+    line.clear();
+    reader.read_line(&mut line).map_err(|err| err.to_string())?;
+    serde_json::from_str(&line).map_err(|err| format!("invalid message: {}", err))
+}
+
+/// 比较令牌，耗时与不匹配的位置无关
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(unix)]
+fn bind(dir: &std::path::Path, name: &str) -> io::Result<Listener> {
+    // 已持有锁，残留的套接字文件一定来自已退出的进程
+    let path = dir.join(format!("{}.sock", name));
+    let _ = fs::remove_file(&path);
+    Listener::bind(path)
+}
+
+#[cfg(unix)]
+fn connect(dir: &std::path::Path, name: &str) -> io::Result<Stream> {
+    Stream::connect(dir.join(format!("{}.sock", name)))
+}
+
+#[cfg(not(unix))]
+fn bind(dir: &std::path::Path, name: &str) -> io::Result<Listener> {
+    // 没有 Unix 套接字时使用回环地址，端口号写入单独的文件
+    let listener = Listener::bind(("127.0.0.1", 0))?;
+    let port = listener.local_addr()?.port();
+    fs::write(dir.join(format!("{}.port", name)), port.to_string())?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn connect(dir: &std::path::Path, name: &str) -> io::Result<Stream> {
+    let port: u16 = fs::read_to_string(dir.join(format!("{}.port", name)))?
+        .trim()
+        .parse()
+        .map_err(io::Error::other)?;
+    Stream::connect(("127.0.0.1", port))
+}
+
+impl InstanceGuard {
+    /// 在后台线程中处理其他实例发来的消息
+    pub fn serve(&self, app: &AppHandle) {
+        let Some(listener) = self.listener.lock().unwrap().take() else {
+            return;
+        };
+        let app = app.clone();
+        let token = self.token.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(err) = handle_connection(&app, stream, &token) {
+                    log::warn!("instance connection failed: {}", err);
+                }
+            }
+        });
+    }
+}
+
+fn handle_connection(app: &AppHandle, stream: Stream, token: &str) -> io::Result<()> {
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+
+    let message = read_request(&mut reader, token);
+    let reply = match &message {
+        Ok(message) => handle_message(app, message),
+        Err(err) => {
+            log::warn!("rejected instance message: {}", err);
+            InstanceReply::error(err.clone())
+        }
+    };
+
+    let mut line = serde_json::to_string(&reply).map_err(io::Error::other)?;
+    line.push('\n');
+    reader.get_mut().write_all(line.as_bytes())?;
+
+    // 回复发送后再退出，保证客户端能收到结果
+    if let Ok(InstanceMessage::Control {
+        command: ControlCommand::Quit,
+    }) = message
+    {
+        handle_tray_menu_event(app, MenuEvent { id: "quit".into() });
+    }
+    Ok(())
+}
+
+fn handle_message(app: &AppHandle, message: &InstanceMessage) -> InstanceReply {
+    match message {
+        InstanceMessage::Activate { args, cwd } => {
+            handle_open_event(app);
+            if let Some(emitter) = app.try_state::<EventEmitter>() {
+                let _ = emitter.emit_second_instance(SecondInstancePayload {
+                    args: args.clone(),
+                    cwd: cwd.clone(),
+                });
+            }
+            InstanceReply::ok()
+        }
+        InstanceMessage::Control { command } => handle_control(app, *command),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(token: &str) -> Vec<u8> {
+        let message = InstanceMessage::Control {
+            command: ControlCommand::Quit,
+        };
+        encode_request(token, &message).unwrap()
+    }
+
+    #[test]
+    fn accepts_requests_with_matching_token() {
+        let token = generate_token().unwrap();
+        let message = read_request(&mut request(&token).as_slice(), &token).unwrap();
+        assert!(matches!(
+            message,
+            InstanceMessage::Control {
+                command: ControlCommand::Quit
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_requests_without_matching_token() {
+        let token = generate_token().unwrap();
+        let other = generate_token().unwrap();
+        assert_ne!(token, other);
+        assert_eq!(token.len(), 64);
+
+        for bytes in [
+            request(&other),
+            request(""),
+            request(&token[..63]),
+            // 旧格式：没有令牌，直接发送消息
+            b"{\"type\":\"control\",\"command\":\"quit\"}\n".to_vec(),
+            Vec::new(),
+        ] {
+            let result = read_request(&mut bytes.as_slice(), &token);
+            assert_eq!(result.unwrap_err(), "invalid instance token");
+        }
+    }
+
+    #[test]
+    fn token_file_is_private() {
+        let dir = std::env::temp_dir().join(format!("echo-instance-{}", std::process::id()));
+        create_private_dir(&dir).unwrap();
+        let path = dir.join("echo.token");
+        write_private(&path, "first").unwrap();
+        write_private(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_contention_counts_as_another_instance() {
+        assert!(is_locked(Ok(())).unwrap());
+        assert!(!is_locked(Err(TryLockError::WouldBlock)).unwrap());
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        let err = is_locked(Err(TryLockError::Error(denied))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        // 同一进程内的另一个句柄也会因锁被占用而失败
+        let dir = std::env::temp_dir().join(format!("echo-lock-{}", std::process::id()));
+        create_private_dir(&dir).unwrap();
+        let path = dir.join("echo.lock");
+        let first = File::create(&path).unwrap();
+        let second = File::open(&path).unwrap();
+        assert!(is_locked(first.try_lock()).unwrap());
+        assert!(!is_locked(second.try_lock()).unwrap());
+        drop(first);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// 日志文件名
+const LOG_FILE_NAME: &str = "echo.log";
+
+/// 单个日志文件的最大大小，超过后轮转
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// 保留的历史日志文件数量（echo.1.log ~ echo.5.log）
+const MAX_ROTATED_FILES: usize = 5;
+
+/// 内存中保留的最近日志条数
+const RECENT_CAPACITY: usize = 1000;
+
+/// 默认日志级别
+#[cfg(debug_assertions)]
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Debug;
+#[cfg(not(debug_assertions))]
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// 一条日志记录
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    fn line(&self) -> String {
+        format!(
+            "{} [{:<5}] {}: {}\n",
+            self.timestamp, self.level, self.target, self.message
+        )
+    }
+}
+
+/// 当前日志文件
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE_NAME))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        if self.size + line.len() as u64 > MAX_FILE_SIZE {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// 轮转：echo.4.log -> echo.5.log ... echo.log -> echo.1.log
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = |n: usize| self.dir.join(format!("echo.{}.log", n));
+        let _ = fs::remove_file(rotated(MAX_ROTATED_FILES));
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let _ = fs::rename(rotated(n), rotated(n + 1));
+        }
+        fs::rename(self.dir.join(LOG_FILE_NAME), rotated(1))?;
+        *self = Self::open(&self.dir.clone())?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    recent: VecDeque<LogEntry>,
+    file: Option<LogFile>,
+}
+
+/// 应用日志记录器
+///
+/// 同时写入内存环形缓冲区（供 `get_recent_logs` 查询）和日志目录下的轮转文件。
+/// 日志目录在应用创建之后才能确定，在此之前的日志只保存在内存中，
+/// 调用 `attach_log_dir` 时一并写入文件。
+struct Logger {
+    inner: Mutex<Inner>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+impl Logger {
+    /// 不等待锁的 `recent`：锁被占用或已中毒时返回 `None`
+    fn try_recent(&self, limit: usize) -> Option<Vec<LogEntry>> {
+        let inner = self.inner.try_lock().ok()?;
+        let skip = inner.recent.len().saturating_sub(limit);
+        Some(inner.recent.iter().skip(skip).cloned().collect())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = LogEntry {
+            timestamp: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        let line = entry.line();
+
+        #[cfg(debug_assertions)]
+        eprint!("{}", line);
+
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(file) = inner.file.as_mut() {
+            let _ = file.write(&line);
+        }
+        if inner.recent.len() == RECENT_CAPACITY {
+            inner.recent.pop_front();
+        }
+        inner.recent.push_back(entry);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.inner.lock().unwrap().file.as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// 初始化日志记录器，`level` 为 `None` 时使用默认级别
+pub fn init(level: Option<LevelFilter>) {
+    let logger = LOGGER.get_or_init(|| Logger {
+        inner: Mutex::new(Inner::default()),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level.unwrap_or(DEFAULT_LEVEL));
+    }
+}
+
+/// 开始写入日志文件，并补写此前只保存在内存中的日志
+pub fn attach_log_dir(dir: &Path) -> io::Result<()> {
+    let Some(logger) = LOGGER.get() else {
+        return Ok(());
+    };
+
+    let mut file = LogFile::open(dir)?;
+    let mut inner = logger.inner.lock().unwrap();
+    for entry in &inner.recent {
+        file.write(&entry.line())?;
+    }
+    inner.file = Some(file);
+    Ok(())
+}
+
+/// 当前日志级别
+pub fn level() -> LevelFilter {
+    log::max_level()
+}
+
+/// 运行时调整日志级别
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// 最近的日志，按时间顺序排列，最多 `limit` 条
+pub fn recent(limit: usize) -> Vec<LogEntry> {
+    let Some(logger) = LOGGER.get() else {
+        return Vec::new();
+    };
+    let inner = logger.inner.lock().unwrap();
+    let skip = inner.recent.len().saturating_sub(limit);
+    inner.recent.iter().skip(skip).cloned().collect()
+}
+
+/// 最近的日志，用于 panic 钩子
+///
+/// panic 可能发生在持有日志锁期间（同一线程再次加锁会死锁），也可能使锁中毒，
+/// 此时不读取日志，返回 `None`。
+pub fn try_recent(limit: usize) -> Option<Vec<LogEntry>> {
+    LOGGER.get()?.try_recent(limit)
+}
+
+/// 记录前端转发的日志
+pub fn log_frontend(level: Level, message: &str) {
+    log::log!(target: "frontend", level, "{}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn logger_with(messages: &[&str]) -> Logger {
+        let mut inner = Inner::default();
+        for message in messages {
+            inner.recent.push_back(LogEntry {
+                timestamp: String::new(),
+                level: "INFO".into(),
+                target: "test".into(),
+                message: message.to_string(),
+            });
+        }
+        Logger {
+            inner: Mutex::new(inner),
+        }
+    }
+
+    #[test]
+    fn try_recent_returns_latest_entries() {
+        let logger = logger_with(&["a", "b", "c"]);
+        let recent = logger.try_recent(2).unwrap();
+        let messages: Vec<_> = recent.iter().map(|entry| entry.message.as_str()).collect();
+        assert_eq!(messages, ["b", "c"]);
+    }
+
+    #[test]
+    fn try_recent_skips_locked_or_poisoned_logger() {
+        let logger = Arc::new(logger_with(&["a"]));
+        {
+            let _held = logger.inner.lock().unwrap();
+            assert!(logger.try_recent(10).is_none());
+        }
+
+        let poisoner = Arc::clone(&logger);
+        let _ = std::thread::spawn(move || {
+            let _held = poisoner.inner.lock().unwrap();
+            panic!("poison the logger");
+        })
+        .join();
+        assert!(logger.inner.is_poisoned());
+        assert!(logger.try_recent(10).is_none());
+    }
+}
@@ -0,0 +1,370 @@
+use crate::core::capabilities;
+use crate::core::commands::command_schemas;
+use crate::core::error::{AppError, AppResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::{Manager, Wry};
+
+/// 单个命令的调用统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CommandMetrics {
+    pub command: String,
+    /// 成功分发的调用次数
+    pub calls: u64,
+    /// 因权限或参数校验被拒绝的次数
+    pub rejected: u64,
+    /// 累计耗时（毫秒）
+    pub total_ms: f64,
+    /// 单次最长耗时（毫秒）
+    pub max_ms: f64,
+    /// 最近一次调用来自的窗口
+    pub last_window: Option<String>,
+}
+
+/// 命令调用统计，由 `with_middleware` 记录，`get_command_metrics` 查询
+#[derive(Default)]
+pub struct CommandStats {
+    metrics: Mutex<BTreeMap<String, CommandMetrics>>,
+}
+
+impl CommandStats {
+    fn record(&self, command: &str, window: &str, outcome: Result<Duration, ()>) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let entry = metrics
+            .entry(command.to_string())
+            .or_insert_with(|| CommandMetrics {
+                command: command.to_string(),
+                ..Default::default()
+            });
+        entry.last_window = Some(window.to_string());
+        match outcome {
+            Ok(elapsed) => {
+                let ms = elapsed.as_secs_f64() * 1000.0;
+                entry.calls += 1;
+                entry.total_ms += ms;
+                entry.max_ms = entry.max_ms.max(ms);
+            }
+            Err(()) => entry.rejected += 1,
+        }
+    }
+
+    /// 当前统计，按命令名排序
+    pub fn snapshot(&self) -> Vec<CommandMetrics> {
+        self.metrics.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// 为命令处理器加上中间件：窗口权限检查、参数校验、日志、计时和统计
+///
+/// 当前所有命令都是同步命令，会在处理器返回前执行完毕，因此处理器的耗时即命令耗时。
+/// 被拒绝的调用直接以 `AppError` 回复前端，不会进入命令处理器。
+///
+/// 只包装应用自己的 `invoke_handler`：插件命令（`plugin:*`）由 Tauri 直接分发给插件，
+/// 不经过这里的检查和统计，其权限由 capabilities 中的插件权限控制。
+pub fn with_middleware<H>(
+    handler: H,
+) -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + Clone + 'static
+where
+    H: Fn(Invoke<Wry>) -> bool + Send + Sync + Clone + 'static,
+{
+    move |invoke: Invoke<Wry>| {
+        let command = invoke.message.command().to_string();
+        let webview = invoke.message.webview();
+        let window = webview.label().to_string();
+        let stats = webview.try_state::<CommandStats>();
+
+        let checked =
+            authorize(&window, &command).and_then(|_| validate(&command, invoke.message.payload()));
+        if let Err(err) = checked {
+            log::warn!("command `{}` from `{}` rejected: {}", command, window, err);
+            if let Some(stats) = &stats {
+                stats.record(&command, &window, Err(()));
+            }
+            invoke.resolver.reject(err);
+            return true;
+        }
+
+        let start = Instant::now();
+        let handled = handler(invoke);
+        let elapsed = start.elapsed();
+        if handled {
+            log::debug!(
+                "command `{}` from `{}` took {:.2?}",
+                command,
+                window,
+                elapsed
+            );
+            if let Some(stats) = &stats {
+                stats.record(&command, &window, Ok(elapsed));
+            }
+        } else {
+            log::warn!("unknown command `{}` from `{}`", command, window);
+        }
+        handled
+    }
+}
+
+/// 按窗口能力检查是否允许调用命令
+fn authorize(window: &str, command: &str) -> AppResult<()> {
+    if !capabilities::allows_command(window, command) {
+        return Err(AppError::PermissionDenied(format!(
+            "command `{}` is not allowed from window `{}`",
+            command, window
+        )));
+    }
+    Ok(())
+}
+
+/// 按命令目录中的 schema 校验参数
+///
+/// 未知命令交给处理器处理；二进制负载不校验。
+fn validate(command: &str, payload: &InvokeBody) -> AppResult<()> {
+    static SCHEMAS: OnceLock<HashMap<&'static str, Value>> = OnceLock::new();
+    let schemas = SCHEMAS.get_or_init(|| command_schemas().into_iter().collect());
+
+    let (Some(schema), InvokeBody::Json(args)) = (schemas.get(command), payload) else {
+        return Ok(());
+    };
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Ok(());
+    };
+    let args = args.as_object().cloned().unwrap_or_default();
+
+    // 缺失的参数按 `null` 处理，与 Tauri 对 `Option` 参数的反序列化一致
+    for (name, property) in properties {
+        let value = args.get(name).unwrap_or(&Value::Null);
+        check(property, value, schema)
+            .map_err(|err| AppError::InvalidArgument(format!("`{}`{}", name, err)))?;
+    }
+    Ok(())
+}
+
+/// 校验值是否符合 schema，错误信息以出错位置开头（如 `.name: expected string`）
+///
+/// 只支持 `schemars` 生成的子集：`$ref`、`enum`、`const`、`anyOf`/`oneOf`/`allOf`、
+/// `type`、`properties`/`required`、`items` 和 `minimum`。
+fn check(schema: &Value, value: &Value, root: &Value) -> Result<(), String> {
+    let Some(object) = schema.as_object() else {
+        return match schema {
+            Value::Bool(false) => Err(": not allowed".to_string()),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        let name = reference.rsplit('/').next().unwrap_or_default();
+        let target = root
+            .get("definitions")
+            .and_then(|definitions| definitions.get(name))
+            .ok_or_else(|| format!(": unknown schema `{}`", reference))?;
+        return check(target, value, root);
+    }
+    if let Some(values) = object.get("enum").and_then(Value::as_array) {
+        if !values.contains(value) {
+            return Err(format!(
+                ": expected one of {}",
+                Value::Array(values.clone())
+            ));
+        }
+    }
+    if let Some(expected) = object.get("const") {
+        if expected != value {
+            return Err(format!(": expected {}", expected));
+        }
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(variants) = object.get(key).and_then(Value::as_array) {
+            let errors: Vec<String> = variants
+                .iter()
+                .filter_map(|variant| check(variant, value, root).err())
+                .collect();
+            if errors.len() == variants.len() {
+                return Err(errors.join(" or "));
+            }
+        }
+    }
+    if let Some(all) = object.get("allOf").and_then(Value::as_array) {
+        for variant in all {
+            check(variant, value, root)?;
+        }
+    }
+
+    if let Some(types) = object.get("type") {
+        let matches = |ty: &str| match ty {
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        };
+        let ok = match types {
+            Value::String(ty) => matches(ty),
+            Value::Array(types) => types.iter().filter_map(Value::as_str).any(matches),
+            _ => true,
+        };
+        if !ok {
+            return Err(format!(": expected {}, got {}", types, type_name(value)));
+        }
+    }
+
+    if let (Some(minimum), Some(number)) = (
+        object.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if number < minimum {
+            return Err(format!(": must be at least {}", minimum));
+        }
+    }
+
+    if let Some(fields) = value.as_object() {
+        if let Some(required) = object.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(key) {
+                    return Err(format!(".{}: missing field", key));
+                }
+            }
+        }
+        if let Some(properties) = object.get("properties").and_then(Value::as_object) {
+            for (key, property) in properties {
+                if let Some(field) = fields.get(key) {
+                    check(property, field, root).map_err(|err| format!(".{}{}", key, err))?;
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(elements)) = (object.get("items"), value.as_array()) {
+        for (index, element) in elements.iter().enumerate() {
+            check(items, element, root).map_err(|err| format!("[{}]{}", index, err))?;
+        }
+    }
+    Ok(())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate_json(command: &str, args: Value) -> Result<(), String> {
+        validate(command, &InvokeBody::Json(args)).map_err(|err| match err {
+            AppError::InvalidArgument(message) => message,
+            other => panic!("unexpected error: {:?}", other),
+        })
+    }
+
+    #[test]
+    fn checks_required_and_optional_args() {
+        assert!(validate_json("greet", json!({ "params": { "name": "Echo" } })).is_ok());
+        assert_eq!(
+            validate_json("greet", json!({ "params": {} })).unwrap_err(),
+            "`params`.name: missing field"
+        );
+        assert_eq!(
+            validate_json("greet", json!({})).unwrap_err(),
+            "`params`: expected \"object\", got null"
+        );
+        // `Option` 参数可以省略或为 null
+        assert!(validate_json("get_recent_logs", json!({})).is_ok());
+        assert!(validate_json("get_recent_logs", json!({ "limit": null })).is_ok());
+        // 带 `#[serde(default)]` 的字段可以省略
+        assert!(validate_json("add_tray_menu_item", json!({ "item": { "id": "x" } })).is_ok());
+        assert_eq!(
+            validate_json("add_tray_menu_item", json!({ "item": {} })).unwrap_err(),
+            "`item`.id: missing field"
+        );
+    }
+
+    #[test]
+    fn rejects_type_mismatches() {
+        assert_eq!(
+            validate_json("set_autostart", json!({ "enabled": "yes" })).unwrap_err(),
+            "`enabled`: expected \"boolean\", got string"
+        );
+        assert!(validate_json("get_recent_logs", json!({ "limit": 1.5 }))
+            .unwrap_err()
+            .starts_with("`limit`: expected"));
+        assert_eq!(
+            validate_json("get_recent_logs", json!({ "limit": -1 })).unwrap_err(),
+            "`limit`: must be at least 0"
+        );
+    }
+
+    #[test]
+    fn checks_enums() {
+        assert!(validate_json("set_tray_status", json!({ "state": "busy" })).is_ok());
+        let err = validate_json("set_tray_status", json!({ "state": "sleeping" })).unwrap_err();
+        assert!(err.starts_with("`state`: expected one of"), "{}", err);
+    }
+
+    #[test]
+    fn resolves_refs() {
+        let root = json!({
+            "definitions": {
+                "Inner": { "type": "object", "required": ["id"], "properties": { "id": { "type": "string" } } },
+                "Outer": { "type": "object", "properties": { "inner": { "$ref": "#/definitions/Inner" } } },
+            }
+        });
+        let outer = json!({ "$ref": "#/definitions/Outer" });
+        assert!(check(&outer, &json!({ "inner": { "id": "a" } }), &root).is_ok());
+        assert_eq!(
+            check(&outer, &json!({ "inner": { "id": 1 } }), &root).unwrap_err(),
+            ".inner.id: expected \"string\", got number"
+        );
+        assert_eq!(
+            check(
+                &json!({ "$ref": "#/definitions/Missing" }),
+                &json!(1),
+                &root
+            )
+            .unwrap_err(),
+            ": unknown schema `#/definitions/Missing`"
+        );
+    }
+
+    #[test]
+    fn reports_paths_in_nested_arrays() {
+        let item = json!({ "item": { "id": "a", "items": [
+            { "id": "b" },
+            { "id": "c", "items": [{ "id": "d", "kind": "nope" }] },
+        ] } });
+        let err = validate_json("add_tray_menu_item", item).unwrap_err();
+        assert!(
+            err.starts_with("`item`.items[1].items[0].kind: expected one of"),
+            "{}",
+            err
+        );
+
+        let matrix = json!({ "type": "array", "items": { "type": "array", "items": { "type": "integer" } } });
+        assert!(check(&matrix, &json!([[1, 2], []]), &Value::Null).is_ok());
+        assert_eq!(
+            check(&matrix, &json!([[1], [2, "3"]]), &Value::Null).unwrap_err(),
+            "[1][1]: expected \"integer\", got string"
+        );
+    }
+
+    #[test]
+    fn skips_unknown_commands_and_binary_payloads() {
+        assert!(validate_json("plugin:opener|open_url", json!({ "url": 1 })).is_ok());
+        assert!(validate("set_autostart", &InvokeBody::Raw(vec![1, 2, 3])).is_ok());
+    }
+}
@@ -1,2 +1,10 @@
+pub mod bindings;
+pub mod capabilities;
 pub mod commands;
+pub mod control;
+pub mod crash;
+pub mod error;
 pub mod events;
+pub mod instance;
+pub mod logger;
+pub mod middleware;
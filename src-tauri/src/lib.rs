@@ -3,20 +3,93 @@ mod config;
 mod core;
 mod ui;
 
-use config::setup::setup_app;
+use config::cli::CliArgs;
+use config::plugins::{register_plugins, PluginRegistry};
+use config::setup::{setup_app, AppConfig};
+use config::store::{
+    peek_skip_if_running, resolve_settings_path, SettingsStore, SETTINGS_FILE_NAME,
+};
 use core::commands::get_command_handlers;
-use tauri_plugin_autostart::MacosLauncher;
+use core::control;
+use core::crash::{self, resolve_crash_dir};
+use core::instance::{self, Acquire};
+use core::logger;
+use core::middleware::{with_middleware, CommandStats};
+
+/// 客户端模式：命令行带有子命令时发送给运行中的实例
+///
+/// 返回 `Some(退出码)` 表示已作为客户端处理；`None` 表示应正常启动应用。
+pub fn run_client() -> Option<i32> {
+    let cli = CliArgs::from_env();
+    let command = cli.command?;
+    Some(control::run_client(&cli, command))
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_autostart::init(
-            MacosLauncher::LaunchAgent,
-            Some(vec!["--flag1", "--flag2"]),
-        ))
-        .invoke_handler(get_command_handlers())
-        .setup(setup_app)
-        .run(tauri::generate_context!())
+    let cli = CliArgs::from_env();
+    logger::init(
+        cli.log_level
+            .as_deref()
+            .and_then(|level| level.parse().ok()),
+    );
+
+    // 尽早安装 panic 钩子，release 构建中 panic 会直接终止进程
+    let context = tauri::generate_context!();
+    if let Some(dir) = resolve_crash_dir(&context.config().identifier) {
+        crash::install_panic_hook(dir, env!("CARGO_PKG_VERSION"));
+    }
+
+    // 无法确定配置目录时使用临时目录中的默认配置，而不是直接退出
+    let identifier = &context.config().identifier;
+    let settings_path = resolve_settings_path(identifier, &cli).unwrap_or_else(|err| {
+        let fallback = std::env::temp_dir()
+            .join(identifier)
+            .join(SETTINGS_FILE_NAME);
+        log::error!("{}; using default settings at {}", err, fallback.display());
+        fallback
+    });
+
+    // 单实例：已有实例在运行时转发参数后退出；
+    // 由系统自动启动且配置了 `skip_if_running` 时不唤起已有实例。
+    // 此时只读取该字段，迁移、备份等写入留给主实例
+    let skip = cli.autostarted && peek_skip_if_running(&settings_path);
+    let args = (!skip).then(|| std::env::args().skip(1).collect());
+    let guard = match instance::acquire(cli.profile.as_deref(), args) {
+        Ok(Acquire::Primary(guard)) => guard,
+        Ok(Acquire::Forwarded) => return,
+        Ok(Acquire::AlreadyRunning) => {
+            log::info!("another instance is already running, skipping autostart");
+            return;
+        }
+        Err(err) => {
+            // 无法确认是否已有实例在运行时不启动，避免出现两个主实例
+            log::error!("single instance check failed: {}", err);
+            eprintln!("error: single instance check failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // 在创建应用之前加载配置，插件需要根据配置注册
+    let store = SettingsStore::load(settings_path);
+    if cli.reset_settings {
+        if let Err(err) = store.set(AppConfig::default()) {
+            log::error!("failed to reset settings: {}", err);
+        }
+    }
+    let config = store.get();
+
+    let registry = PluginRegistry::default();
+    let builder = tauri::Builder::default()
+        .manage(cli)
+        .manage(store)
+        .manage(guard)
+        .manage(CommandStats::default());
+
+    register_plugins(builder, &registry, &config)
+        .manage(registry)
+        .invoke_handler(with_middleware(get_command_handlers()))
+        .setup(|app| Ok(setup_app(app)?))
+        .run(context)
         .expect("error while running tauri application");
 }
@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // 带子命令（show / hide / popover / quit / status）时作为客户端控制已运行的实例
+    if let Some(code) = echo_lib::run_client() {
+        std::process::exit(code);
+    }
+
     echo_lib::run()
 }
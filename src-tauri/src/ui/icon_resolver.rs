@@ -0,0 +1,441 @@
+use crate::config::store::SettingsStore;
+use crate::core::error::{AppError, AppResult};
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// 编译进程序的图标，所有文件位置都失败时使用
+const EMBEDDED: &[(&str, &[u8])] = &[
+    ("icons/32x32.png", include_bytes!("../../icons/32x32.png")),
+    (
+        "icons/128x128.png",
+        include_bytes!("../../icons/128x128.png"),
+    ),
+];
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// ICNS 中以 PNG 保存的图像类型及其像素尺寸
+const ICNS_TYPES: &[(&[u8; 4], u32)] = &[
+    (b"icp4", 16),
+    (b"icp5", 32),
+    (b"icp6", 64),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic09", 512),
+    (b"ic10", 1024),
+    (b"ic11", 32),
+    (b"ic12", 64),
+    (b"ic13", 256),
+    (b"ic14", 512),
+];
+
+/// 图标解析器
+///
+/// 按固定顺序查找图标：资源目录 → 配置目录（用户覆盖）→ 内置图标。
+/// SVG 按目标尺寸渲染，ICO/ICNS 选择最合适的图像，大于目标尺寸的图像等比缩小。
+/// 解码结果按名称和像素尺寸缓存。
+pub struct IconResolver {
+    resource_dirs: Vec<PathBuf>,
+    override_dir: Option<PathBuf>,
+    cache: Mutex<HashMap<(String, u32), RgbaImage>>,
+}
+
+impl IconResolver {
+    pub fn new(resource_dirs: Vec<PathBuf>, override_dir: Option<PathBuf>) -> Self {
+        Self {
+            resource_dirs,
+            override_dir,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 使用应用的资源目录和配置文件所在目录
+    pub fn for_app(app: &AppHandle) -> Self {
+        let mut resource_dirs = Vec::new();
+        if let Ok(dir) = app.path().resource_dir() {
+            resource_dirs.push(dir);
+        }
+        // 开发环境下资源不会复制到可执行文件旁边
+        if cfg!(debug_assertions) {
+            resource_dirs.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+        }
+        let override_dir = app
+            .try_state::<SettingsStore>()
+            .and_then(|store| store.path().parent().map(Path::to_path_buf));
+        Self::new(resource_dirs, override_dir)
+    }
+
+    /// 加载图标，`size` 为逻辑尺寸，按 `scale_factor` 换算为像素尺寸
+    pub fn resolve(&self, name: &str, size: u32, scale_factor: f64) -> AppResult<RgbaImage> {
+        let target = (size as f64 * scale_factor).round().max(1.0) as u32;
+        let key = (name.to_string(), target);
+        if let Some(image) = self.cache.lock().unwrap().get(&key) {
+            return Ok(image.clone());
+        }
+
+        let image = self.search(name, |bytes| decode(name, bytes, target))?;
+        self.cache.lock().unwrap().insert(key, image.clone());
+        Ok(image)
+    }
+
+    /// 按相同顺序读取图标文件的原始内容（例如动画帧）
+    pub fn read(&self, name: &str) -> AppResult<Vec<u8>> {
+        self.search(name, |bytes| Ok(bytes.to_vec()))
+    }
+
+    /// 依次尝试每个位置，全部失败时返回包含每个路径及失败原因的错误
+    fn search<T>(
+        &self,
+        name: &str,
+        mut load: impl FnMut(&[u8]) -> Result<T, String>,
+    ) -> AppResult<T> {
+        let relative = Path::new(name);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(AppError::InvalidArgument(format!(
+                "icon path must be relative: {}",
+                name
+            )));
+        }
+
+        let locations = self
+            .resource_dirs
+            .iter()
+            .map(|dir| ("resource", dir))
+            .chain(self.override_dir.iter().map(|dir| ("override", dir)));
+        let mut failures = Vec::new();
+        for (kind, dir) in locations {
+            let path = dir.join(relative);
+            let result = fs::read(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| load(&bytes));
+            match result {
+                Ok(value) => return Ok(loaded(name, &failures, value)),
+                Err(err) => failures.push(format!("{} {}: {}", kind, path.display(), err)),
+            }
+        }
+
+        match EMBEDDED.iter().find(|(embedded, _)| *embedded == name) {
+            Some((_, bytes)) => match load(bytes) {
+                Ok(value) => return Ok(loaded(name, &failures, value)),
+                Err(err) => failures.push(format!("embedded {}: {}", name, err)),
+            },
+            None => failures.push(format!("embedded {}: not embedded", name)),
+        }
+
+        Err(AppError::NotFound(format!(
+            "icon {} not found ({})",
+            name,
+            failures.join("; ")
+        )))
+    }
+}
+
+/// 前面的位置失败后才加载成功时记录失败原因
+fn loaded<T>(name: &str, failures: &[String], value: T) -> T {
+    if !failures.is_empty() {
+        log::debug!("icon {} loaded after: {}", name, failures.join("; "));
+    }
+    value
+}
+
+/// 按格式解码图标，`target` 为目标像素尺寸；大于目标尺寸的图像等比缩小
+fn decode(name: &str, bytes: &[u8], target: u32) -> Result<RgbaImage, String> {
+    if is_svg(name, bytes) {
+        return render_svg(bytes, target);
+    }
+    let image = if bytes.starts_with(&[0, 0, 1, 0]) {
+        decode_ico(bytes, target)?
+    } else if bytes.starts_with(b"icns") {
+        decode_icns(bytes, target)?
+    } else {
+        image::load_from_memory(bytes)
+            .map_err(|err| err.to_string())?
+            .to_rgba8()
+    };
+    Ok(shrink_to_fit(image, target))
+}
+
+fn shrink_to_fit(image: RgbaImage, target: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let longest = width.max(height);
+    if longest <= target {
+        return image;
+    }
+    let scale = |value: u32| ((value as u64 * target as u64) / longest as u64).max(1) as u32;
+    imageops::resize(
+        &image,
+        scale(width),
+        scale(height),
+        imageops::FilterType::Lanczos3,
+    )
+}
+
+fn is_svg(name: &str, bytes: &[u8]) -> bool {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    if matches!(extension.as_deref(), Some("svg" | "svgz")) {
+        return true;
+    }
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with("<svg") || head.starts_with("<?xml")
+}
+
+/// 把 SVG 等比缩放后居中渲染到 `target`×`target` 的图像
+fn render_svg(bytes: &[u8], target: u32) -> Result<RgbaImage, String> {
+    let tree =
+        usvg::Tree::from_data(bytes, &usvg::Options::default()).map_err(|err| err.to_string())?;
+    let size = tree.size();
+    let scale = target as f32 / size.width().max(size.height());
+    let dx = (target as f32 - size.width() * scale) / 2.0;
+    let dy = (target as f32 - size.height() * scale) / 2.0;
+
+    let mut pixmap =
+        tiny_skia::Pixmap::new(target, target).ok_or_else(|| "invalid icon size".to_string())?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, dx, dy),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia 使用预乘 alpha
+    let mut image = RgbaImage::new(target, target);
+    for (pixel, color) in image.pixels_mut().zip(pixmap.pixels()) {
+        let color = color.demultiply();
+        *pixel = Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    Ok(image)
+}
+
+/// 选择最合适的图像：不小于目标尺寸中最小的，都更小时取最大的；尺寸相同时取色深更高的
+fn best_index(candidates: &[(u32, u16)], target: u32) -> Option<usize> {
+    let entries = candidates.iter().enumerate();
+    entries
+        .clone()
+        .filter(|(_, (size, _))| *size >= target)
+        .min_by_key(|(_, (size, depth))| (*size, Reverse(*depth)))
+        .or_else(|| entries.max_by_key(|(_, (size, depth))| (*size, *depth)))
+        .map(|(index, _)| index)
+}
+
+/// 从 ICO 目录中选择图像；BMP 图像重新包装成只有一项的 ICO 再解码
+fn decode_ico(bytes: &[u8], target: u32) -> Result<RgbaImage, String> {
+    let count = bytes
+        .get(4..6)
+        .map(|count| u16::from_le_bytes([count[0], count[1]]) as usize)
+        .ok_or_else(|| "truncated ICO header".to_string())?;
+
+    let mut entries = Vec::with_capacity(count);
+    for index in 0..count {
+        let offset = 6 + index * 16;
+        let entry = bytes
+            .get(offset..offset + 16)
+            .ok_or_else(|| "truncated ICO directory".to_string())?;
+        let dimension = |value: u8| if value == 0 { 256 } else { value as u32 };
+        let size = dimension(entry[0]).max(dimension(entry[1]));
+        let depth = u16::from_le_bytes([entry[6], entry[7]]);
+        let length = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]) as usize;
+        let start = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]) as usize;
+        let data = start
+            .checked_add(length)
+            .and_then(|end| bytes.get(start..end))
+            .ok_or_else(|| format!("ICO entry {} is out of bounds", index))?;
+        entries.push((size, depth, entry, data));
+    }
+
+    let sizes: Vec<_> = entries
+        .iter()
+        .map(|(size, depth, ..)| (*size, *depth))
+        .collect();
+    let index = best_index(&sizes, target).ok_or_else(|| "ICO has no images".to_string())?;
+    let (_, _, entry, data) = entries[index];
+
+    let image = if data.starts_with(PNG_SIGNATURE) {
+        image::load_from_memory_with_format(data, ImageFormat::Png)
+    } else {
+        let mut single = vec![0, 0, 1, 0, 1, 0];
+        single.extend_from_slice(&entry[..12]);
+        single.extend_from_slice(&22u32.to_le_bytes());
+        single.extend_from_slice(data);
+        image::load_from_memory_with_format(&single, ImageFormat::Ico)
+    };
+    image
+        .map(|image| image.to_rgba8())
+        .map_err(|err| err.to_string())
+}
+
+/// 从 ICNS 中选择 PNG 编码的图像；旧式 RLE 和 JPEG 2000 图像会被跳过
+fn decode_icns(bytes: &[u8], target: u32) -> Result<RgbaImage, String> {
+    let total = bytes
+        .get(4..8)
+        .map(|length| u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize)
+        .ok_or_else(|| "truncated ICNS header".to_string())?
+        .min(bytes.len());
+
+    let mut entries = Vec::new();
+    let mut offset = 8;
+    while offset + 8 <= total {
+        let kind = &bytes[offset..offset + 4];
+        let length = u32::from_be_bytes([
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]) as usize;
+        if length < 8 || offset + length > total {
+            return Err(format!("ICNS entry at {} is out of bounds", offset));
+        }
+        let data = &bytes[offset + 8..offset + length];
+        if data.starts_with(PNG_SIGNATURE) {
+            if let Some((_, size)) = ICNS_TYPES.iter().find(|(known, _)| &known[..] == kind) {
+                entries.push((*size, data));
+            }
+        }
+        offset += length;
+    }
+
+    let sizes: Vec<_> = entries.iter().map(|(size, _)| (*size, 32)).collect();
+    let index = best_index(&sizes, target).ok_or_else(|| "ICNS has no PNG images".to_string())?;
+    image::load_from_memory_with_format(entries[index].1, ImageFormat::Png)
+        .map(|image| image.to_rgba8())
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::ico::{IcoEncoder, IcoFrame};
+    use image::ExtendedColorType;
+    use std::io::Cursor;
+
+    fn png(size: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(size, size, Rgba([0xFF, 0x00, 0x00, 0xFF]));
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn ico(sizes: &[u32]) -> Vec<u8> {
+        let frames: Vec<_> = sizes
+            .iter()
+            .map(|&size| {
+                let pixels = vec![0xFF; (size * size * 4) as usize];
+                IcoFrame::as_png(&pixels, size, size, ExtendedColorType::Rgba8).unwrap()
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        IcoEncoder::new(&mut bytes).encode_images(&frames).unwrap();
+        bytes
+    }
+
+    fn icns(entries: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (kind, data) in entries {
+            body.extend_from_slice(&kind[..]);
+            body.extend_from_slice(&(data.len() as u32 + 8).to_be_bytes());
+            body.extend_from_slice(data);
+        }
+        let mut bytes = b"icns".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn picks_smallest_image_not_below_target() {
+        let candidates = [(16, 32), (64, 32), (32, 8), (32, 32), (256, 32)];
+        assert_eq!(best_index(&candidates, 32), Some(3));
+        assert_eq!(best_index(&candidates, 48), Some(1));
+        assert_eq!(best_index(&candidates, 512), Some(4));
+        assert_eq!(best_index(&[], 32), None);
+    }
+
+    #[test]
+    fn selects_ico_frame_for_scale_factor() {
+        let bytes = ico(&[16, 32, 64]);
+        assert_eq!(decode_ico(&bytes, 16).unwrap().width(), 16);
+        assert_eq!(decode_ico(&bytes, 32).unwrap().width(), 32);
+        assert_eq!(decode_ico(&bytes, 48).unwrap().width(), 64);
+        assert_eq!(decode_ico(&bytes, 128).unwrap().width(), 64);
+        // 解码结果缩小到目标尺寸
+        assert_eq!(decode("icon.ico", &bytes, 48).unwrap().width(), 48);
+    }
+
+    #[test]
+    fn selects_icns_png_entry() {
+        let bytes = icns(&[
+            (b"ic07", png(128)),
+            (b"ic11", png(32)),
+            (b"TOC ", vec![0; 8]),
+            (b"ic12", png(64)),
+        ]);
+        assert_eq!(decode_icns(&bytes, 32).unwrap().width(), 32);
+        assert_eq!(decode_icns(&bytes, 48).unwrap().width(), 64);
+        assert_eq!(decode_icns(&bytes, 1024).unwrap().width(), 128);
+        assert!(decode_icns(&icns(&[(b"TOC ", vec![0; 8])]), 32).is_err());
+    }
+
+    #[test]
+    fn renders_svg_at_target_size() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="20">
+            <rect width="10" height="20" fill="#0000ff"/>
+        </svg>"##;
+        let image = decode("tray.svg", svg, 40).unwrap();
+        assert_eq!(image.dimensions(), (40, 40));
+        assert_eq!(image.get_pixel(20, 20), &Rgba([0x00, 0x00, 0xFF, 0xFF]));
+        // 等比缩放后左右两侧透明
+        assert_eq!(image.get_pixel(2, 20)[3], 0);
+    }
+
+    #[test]
+    fn searches_in_order_and_reports_each_path() {
+        let dir = std::env::temp_dir().join(format!("icon-resolver-{}", std::process::id()));
+        let resource = dir.join("resource");
+        let config = dir.join("config");
+        fs::create_dir_all(resource.join("icons")).unwrap();
+        fs::create_dir_all(config.join("icons")).unwrap();
+        fs::write(resource.join("icons/broken.png"), b"not an image").unwrap();
+        fs::write(config.join("icons/broken.png"), png(16)).unwrap();
+
+        let resolver = IconResolver::new(vec![resource.clone()], Some(config.clone()));
+        // 资源目录中的文件无法解码时使用配置目录中的文件
+        assert_eq!(
+            resolver
+                .resolve("icons/broken.png", 16, 1.0)
+                .unwrap()
+                .width(),
+            16
+        );
+        // 缓存命中时不再读取文件
+        fs::remove_file(config.join("icons/broken.png")).unwrap();
+        assert!(resolver.resolve("icons/broken.png", 16, 1.0).is_ok());
+        // 都没有时使用内置图标
+        assert_eq!(
+            resolver
+                .resolve("icons/32x32.png", 16, 2.0)
+                .unwrap()
+                .width(),
+            32
+        );
+
+        let err = resolver.resolve("icons/missing.png", 16, 1.0).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(&resource.join("icons/missing.png").display().to_string()));
+        assert!(message.contains(&config.join("icons/missing.png").display().to_string()));
+        assert!(message.contains("embedded icons/missing.png"));
+        assert!(resolver.read("../settings.json").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
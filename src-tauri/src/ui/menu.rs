@@ -1,6 +1,13 @@
+use crate::core::error::AppResult;
+use crate::ui::window_state;
 use tauri::menu::{MenuBuilder, MenuItem, PredefinedMenuItem, Submenu};
 use tauri::{AppHandle, Manager};
 
+/// 应用菜单项 id
+///
+/// Tauri 的菜单事件是全局的，托盘菜单也会收到这些 id 的点击，前端不能注册同名的托盘菜单项。
+pub const APP_MENU_ITEMS: &[&str] = &["open", "close", "quit", "about"];
+
 /// 格式化菜单项文本，通过添加空格来调整菜单宽度
 ///
 /// # 参数
@@ -22,7 +29,7 @@ fn format_menu_text(text: &str, min_width: usize) -> String {
 }
 
 /// 创建并设置应用菜单
-pub fn setup_menu(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+pub fn setup_menu(app: &AppHandle) -> AppResult<()> {
     // 创建菜单项
     let open_text = format_menu_text("打开窗口", 10);
     let close_text = format_menu_text("关闭窗口 ", 10);
@@ -66,6 +73,7 @@ pub fn setup_menu(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                 handle_close_event(app_handle);
             }
             "quit" => {
+                window_state::save_all(app_handle);
                 app_handle.exit(0);
             }
             "about" => {
@@ -73,7 +81,7 @@ pub fn setup_menu(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
             }
             _ => {
                 // PredefinedMenuItem 的 quit 事件可能使用不同的 ID
-                println!("unexpected menu event: {:?}", event.id());
+                log::warn!("unexpected menu event: {:?}", event.id());
             }
         }
     });
@@ -82,7 +90,7 @@ pub fn setup_menu(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// 处理打开菜单事件
-fn handle_open_event(app_handle: &tauri::AppHandle) {
+pub(crate) fn handle_open_event(app_handle: &tauri::AppHandle) {
     // 在这里添加打开逻辑
     if let Some(window) = app_handle.get_webview_window("main") {
         let _ = window.show();
@@ -91,7 +99,7 @@ fn handle_open_event(app_handle: &tauri::AppHandle) {
 }
 
 /// 处理关闭菜单事件
-fn handle_close_event(app_handle: &tauri::AppHandle) {
+pub(crate) fn handle_close_event(app_handle: &tauri::AppHandle) {
     // 在这里添加关闭逻辑
     if let Some(window) = app_handle.get_webview_window("main") {
         let _ = window.hide();
@@ -101,23 +109,46 @@ fn handle_close_event(app_handle: &tauri::AppHandle) {
 /// 处理关于菜单事件
 /// 创建一个关于窗口显示应用信息（使用前端页面）
 fn handle_about_event(app_handle: &tauri::AppHandle) {
-    // 检查关于窗口是否已经存在
-    if let Some(about_window) = app_handle.get_webview_window("about") {
-        let _ = about_window.show();
-        let _ = about_window.set_focus();
+    // 加载前端的 About 页面，使用路由路径 /about
+    open_page_window(app_handle, "about", "/about", "关于 Echo", (520.0, 600.0));
+}
+
+/// 显示崩溃报告窗口（使用前端页面 /crash-reports）
+pub(crate) fn show_crash_reports(app_handle: &tauri::AppHandle) {
+    open_page_window(
+        app_handle,
+        "crash-reports",
+        "/crash-reports",
+        "崩溃报告",
+        (640.0, 600.0),
+    );
+}
+
+/// 显示一个加载前端页面的窗口，窗口已存在时直接显示并聚焦
+fn open_page_window(
+    app_handle: &tauri::AppHandle,
+    label: &str,
+    route: &str,
+    title: &str,
+    (width, height): (f64, f64),
+) {
+    // 检查窗口是否已经存在
+    if let Some(window) = app_handle.get_webview_window(label) {
+        let _ = window.show();
+        let _ = window.set_focus();
         return;
     }
 
-    // 使用 WebviewWindowBuilder 创建关于窗口
-    // 加载前端的 About 页面，使用路由路径 /about
-    let _ = tauri::WebviewWindowBuilder::new(
-        app_handle,
-        "about",
-        tauri::WebviewUrl::App("/about".into()),
-    )
-    .title("关于 Echo")
-    .inner_size(520.0, 600.0)
-    .resizable(false)
-    .center()
-    .build();
+    // 使用 WebviewWindowBuilder 创建窗口
+    let window =
+        tauri::WebviewWindowBuilder::new(app_handle, label, tauri::WebviewUrl::App(route.into()))
+            .title(title)
+            .inner_size(width, height)
+            .resizable(false)
+            .center()
+            .build();
+    match window {
+        Ok(window) => window_state::track_window(&window),
+        Err(err) => log::error!("failed to open window {}: {}", label, err),
+    }
 }
@@ -1,2 +1,9 @@
+pub mod icon_resolver;
 pub mod menu;
+pub mod placement;
 pub mod tray;
+pub mod tray_animation;
+pub mod tray_icon;
+pub mod tray_menu;
+pub mod tray_theme;
+pub mod window_state;
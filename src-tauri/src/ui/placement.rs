@@ -0,0 +1,310 @@
+//! 托盘弹出窗口定位
+//!
+//! 纯计算模块：根据托盘图标区域、所在显示器的工作区、缩放比例和弹出窗口尺寸，
+//! 判断任务栏（菜单栏、面板、Dock）位于哪条边，把弹出窗口贴着该边放在图标旁，
+//! 并限制在工作区内。所有坐标和尺寸均为物理像素。
+
+/// 弹出窗口与托盘图标之间的间距（逻辑像素）
+pub const GAP: f64 = 8.0;
+
+/// 弹出窗口与工作区边缘的最小距离（逻辑像素）
+pub const MARGIN: f64 = 8.0;
+
+/// 矩形区域（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+/// 任务栏所在的屏幕边
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// 定位结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub x: i32,
+    pub y: i32,
+    /// 弹出窗口贴靠的边
+    pub edge: Edge,
+}
+
+/// 判断托盘图标所在的任务栏位于工作区的哪条边
+///
+/// 工作区不包含任务栏，图标中心在工作区之外时取所在一侧；
+/// 任务栏自动隐藏等情况下图标落在工作区内，取距离最近的边。
+pub fn tray_edge(tray: Rect, work_area: Rect) -> Edge {
+    let (cx, cy) = tray.center();
+    if cy < work_area.y {
+        Edge::Top
+    } else if cy > work_area.bottom() {
+        Edge::Bottom
+    } else if cx < work_area.x {
+        Edge::Left
+    } else if cx > work_area.right() {
+        Edge::Right
+    } else {
+        [
+            (cy - work_area.y, Edge::Top),
+            (work_area.bottom() - cy, Edge::Bottom),
+            (cx - work_area.x, Edge::Left),
+            (work_area.right() - cx, Edge::Right),
+        ]
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, edge)| edge)
+        .unwrap_or(Edge::Bottom)
+    }
+}
+
+/// 计算弹出窗口位置：贴着任务栏所在边放在托盘图标旁，与图标居中对齐，再限制在工作区内
+///
+/// `popover` 为弹出窗口尺寸 `(宽, 高)`。
+pub fn place_popover(
+    tray: Rect,
+    work_area: Rect,
+    scale_factor: f64,
+    popover: (f64, f64),
+) -> Placement {
+    let edge = tray_edge(tray, work_area);
+    let gap = GAP * scale_factor;
+    let (width, height) = popover;
+    let (cx, cy) = tray.center();
+
+    let (x, y) = match edge {
+        Edge::Top => (cx - width / 2.0, tray.bottom().max(work_area.y) + gap),
+        Edge::Bottom => (
+            cx - width / 2.0,
+            tray.y.min(work_area.bottom()) - gap - height,
+        ),
+        Edge::Left => (tray.right().max(work_area.x) + gap, cy - height / 2.0),
+        Edge::Right => (
+            tray.x.min(work_area.right()) - gap - width,
+            cy - height / 2.0,
+        ),
+    };
+    clamp(x, y, edge, work_area, scale_factor, popover)
+}
+
+/// 没有托盘图标位置时，把弹出窗口放在 `edge` 一侧的末端角落（顶部、底部取右角，左右两侧取下角）
+pub fn place_in_corner(
+    work_area: Rect,
+    scale_factor: f64,
+    popover: (f64, f64),
+    edge: Edge,
+) -> Placement {
+    let (width, height) = popover;
+    let (x, y) = match edge {
+        Edge::Top => (work_area.right() - width, work_area.y),
+        Edge::Bottom | Edge::Right => (work_area.right() - width, work_area.bottom() - height),
+        Edge::Left => (work_area.x, work_area.bottom() - height),
+    };
+    clamp(x, y, edge, work_area, scale_factor, popover)
+}
+
+/// 限制在工作区内并保留边距；弹出窗口比工作区还大时对齐到左上角
+fn clamp(
+    x: f64,
+    y: f64,
+    edge: Edge,
+    work_area: Rect,
+    scale_factor: f64,
+    (width, height): (f64, f64),
+) -> Placement {
+    let margin = MARGIN * scale_factor;
+    let axis = |value: f64, start: f64, length: f64, size: f64| {
+        let min = start + margin;
+        let max = start + length - margin - size;
+        if max < min {
+            start
+        } else {
+            value.clamp(min, max)
+        }
+    };
+    Placement {
+        x: axis(x, work_area.x, work_area.width, width).round() as i32,
+        y: axis(y, work_area.y, work_area.height, height).round() as i32,
+        edge,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POPOVER: (f64, f64) = (400.0, 500.0);
+
+    /// 1920x1080 显示器，底部 40px 任务栏
+    fn bottom_taskbar() -> Rect {
+        Rect::new(0.0, 0.0, 1920.0, 1040.0)
+    }
+
+    #[test]
+    fn detects_taskbar_edges() {
+        let cases = [
+            (Rect::new(0.0, 0.0, 1920.0, 1040.0), Edge::Bottom),
+            (Rect::new(0.0, 28.0, 1920.0, 1052.0), Edge::Top),
+            (Rect::new(64.0, 0.0, 1856.0, 1080.0), Edge::Left),
+            (Rect::new(0.0, 0.0, 1856.0, 1080.0), Edge::Right),
+        ];
+        for (work_area, expected) in cases {
+            // 图标位于任务栏中部
+            let tray = match expected {
+                Edge::Bottom => Rect::new(1800.0, 1044.0, 32.0, 32.0),
+                Edge::Top => Rect::new(1800.0, 0.0, 28.0, 28.0),
+                Edge::Left => Rect::new(16.0, 900.0, 32.0, 32.0),
+                Edge::Right => Rect::new(1872.0, 900.0, 32.0, 32.0),
+            };
+            assert_eq!(tray_edge(tray, work_area), expected);
+        }
+    }
+
+    #[test]
+    fn opens_above_bottom_taskbar() {
+        let tray = Rect::new(1500.0, 1044.0, 32.0, 32.0);
+        let placement = place_popover(tray, bottom_taskbar(), 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Bottom);
+        // 与图标居中对齐，图标上方留出间距
+        assert_eq!(placement.x, 1316);
+        assert_eq!(placement.y, 1040 - 8 - 500);
+    }
+
+    #[test]
+    fn opens_below_top_panel() {
+        let work_area = Rect::new(0.0, 28.0, 1920.0, 1052.0);
+        let tray = Rect::new(900.0, 0.0, 28.0, 28.0);
+        let placement = place_popover(tray, work_area, 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Top);
+        assert_eq!((placement.x, placement.y), (714, 36));
+    }
+
+    #[test]
+    fn opens_beside_side_docks() {
+        let left = Rect::new(64.0, 0.0, 1856.0, 1080.0);
+        let tray = Rect::new(16.0, 500.0, 32.0, 32.0);
+        let placement = place_popover(tray, left, 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Left);
+        assert_eq!((placement.x, placement.y), (72, 266));
+
+        let right = Rect::new(0.0, 0.0, 1856.0, 1080.0);
+        let tray = Rect::new(1872.0, 500.0, 32.0, 32.0);
+        let placement = place_popover(tray, right, 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Right);
+        assert_eq!((placement.x, placement.y), (1856 - 8 - 400, 266));
+    }
+
+    #[test]
+    fn clamps_to_work_area_near_corners() {
+        // 图标靠近屏幕右端，居中对齐会超出屏幕
+        let tray = Rect::new(1880.0, 1044.0, 32.0, 32.0);
+        let placement = place_popover(tray, bottom_taskbar(), 1.0, POPOVER);
+        assert_eq!(placement.x, 1920 - 8 - 400);
+
+        // 左侧 Dock 底部的图标
+        let left = Rect::new(64.0, 0.0, 1856.0, 1080.0);
+        let tray = Rect::new(16.0, 1040.0, 32.0, 32.0);
+        let placement = place_popover(tray, left, 1.0, POPOVER);
+        assert_eq!(placement.y, 1080 - 8 - 500);
+    }
+
+    #[test]
+    fn scales_gap_and_margin_on_hidpi() {
+        // 2 倍缩放的 3840x2160 显示器，底部 80px 任务栏
+        let work_area = Rect::new(0.0, 0.0, 3840.0, 2080.0);
+        let popover = (800.0, 1000.0);
+        let tray = Rect::new(2000.0, 2088.0, 64.0, 64.0);
+        let placement = place_popover(tray, work_area, 2.0, popover);
+        assert_eq!(placement.y, 2080 - 16 - 1000);
+
+        let tray = Rect::new(3780.0, 2088.0, 64.0, 64.0);
+        let placement = place_popover(tray, work_area, 2.0, popover);
+        assert_eq!(placement.x, 3840 - 16 - 800);
+    }
+
+    #[test]
+    fn handles_secondary_monitors_with_negative_origin() {
+        // 主显示器左侧的副显示器，顶部面板
+        let work_area = Rect::new(-1920.0, 28.0, 1920.0, 1052.0);
+        let tray = Rect::new(-40.0, 0.0, 28.0, 28.0);
+        let placement = place_popover(tray, work_area, 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Top);
+        assert_eq!((placement.x, placement.y), (-8 - 400, 36));
+
+        // 主显示器上方的副显示器，底部任务栏
+        let work_area = Rect::new(0.0, -1080.0, 1920.0, 1040.0);
+        let tray = Rect::new(20.0, -36.0, 32.0, 32.0);
+        let placement = place_popover(tray, work_area, 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Bottom);
+        assert_eq!((placement.x, placement.y), (8, -40 - 8 - 500));
+    }
+
+    #[test]
+    fn uses_nearest_edge_when_tray_is_inside_work_area() {
+        // 任务栏自动隐藏时工作区覆盖整个屏幕
+        let work_area = Rect::new(0.0, 0.0, 1920.0, 1080.0);
+        let tray = Rect::new(1800.0, 1050.0, 24.0, 24.0);
+        let placement = place_popover(tray, work_area, 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Bottom);
+        assert_eq!(placement.y, 1050 - 8 - 500);
+
+        let tray = Rect::new(1800.0, 2.0, 24.0, 24.0);
+        assert_eq!(tray_edge(tray, work_area), Edge::Top);
+    }
+
+    #[test]
+    fn accepts_click_points_as_empty_rects() {
+        let tray = Rect::new(1516.0, 1060.0, 0.0, 0.0);
+        let placement = place_popover(tray, bottom_taskbar(), 1.0, POPOVER);
+        assert_eq!(placement.edge, Edge::Bottom);
+        assert_eq!((placement.x, placement.y), (1316, 532));
+    }
+
+    #[test]
+    fn keeps_oversized_popovers_at_work_area_origin() {
+        let work_area = Rect::new(0.0, 0.0, 1280.0, 680.0);
+        let tray = Rect::new(1200.0, 690.0, 32.0, 32.0);
+        let placement = place_popover(tray, work_area, 1.0, (1400.0, 800.0));
+        assert_eq!((placement.x, placement.y), (0, 0));
+    }
+
+    #[test]
+    fn places_in_corner_without_tray() {
+        let placement = place_in_corner(bottom_taskbar(), 1.0, POPOVER, Edge::Bottom);
+        assert_eq!((placement.x, placement.y), (1512, 532));
+
+        let work_area = Rect::new(0.0, 25.0, 1440.0, 875.0);
+        let placement = place_in_corner(work_area, 2.0, POPOVER, Edge::Top);
+        assert_eq!((placement.x, placement.y), (1440 - 16 - 400, 25 + 16));
+    }
+}
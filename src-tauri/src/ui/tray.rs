@@ -1,205 +1,246 @@
-use std::path::PathBuf;
-use tauri::menu::Menu;
-use tauri::menu::MenuItem;
+use crate::config::autostart::set_autostart_enabled;
+use crate::config::store::SettingsStore;
+use crate::core::error::{AppError, AppResult};
+use crate::ui::icon_resolver::IconResolver;
+use crate::ui::placement::{self, place_in_corner, place_popover, Edge};
+use crate::ui::tray_animation::TrayAnimation;
+use crate::ui::tray_icon::{IconCache, TrayStatus};
+use crate::ui::tray_menu::{self, build_menu, TrayMenu, TrayMenuItem, TRAY_ID};
+use crate::ui::tray_theme::{load_variant, IconVariant, TrayTheme};
+use crate::ui::window_state;
+use image::RgbaImage;
+use std::sync::Mutex;
+use tauri::image::Image;
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, PhysicalPosition, Theme, Wry};
 
 #[cfg(target_os = "macos")]
 use cocoa::appkit::{NSApplication, NSApplicationActivationPolicy};
 
-/// 加载自定义托盘图标
-///
-/// # 参数
-/// - `app`: 应用句柄
-/// - `icon_path`: 图标文件路径（相对于 src-tauri 目录）
-///
-/// # 返回
-/// 如果成功加载自定义图标，返回该图标；否则返回 None
-fn load_tray_icon(
-    app: &AppHandle,
-    icon_path: Option<&str>,
-) -> Option<tauri::image::Image<'static>> {
-    if let Some(path) = icon_path {
-        // 尝试加载图标的辅助函数
-        let try_load = |file_path: &std::path::Path| -> Option<tauri::image::Image<'static>> {
-            if !file_path.exists() {
-                return None;
-            }
-
-            // 读取文件
-            let bytes = std::fs::read(file_path).ok()?;
+/// 托盘图标的逻辑尺寸，按主显示器的缩放比例换算为像素尺寸
+const TRAY_ICON_SIZE: u32 = 32;
 
-            // 使用 image crate 解码图片
-            let img = image::load_from_memory(&bytes).ok()?;
-            let rgba = img.to_rgba8();
-            let (width, height) = rgba.dimensions();
-
-            // 转换为 Tauri Image
-            Some(tauri::image::Image::new_owned(
-                rgba.into_raw(),
-                width,
-                height,
-            ))
-        };
-
-        // 首先尝试从资源目录加载（生产环境）
-        if let Ok(resource_dir) = app.path().resource_dir() {
-            let icon_file = resource_dir.join(path);
-            if let Some(icon) = try_load(&icon_file) {
-                return Some(icon);
-            }
-        }
-
-        // 尝试从项目目录加载（开发环境）
-        let project_icon = PathBuf::from("src-tauri").join(path);
-        if let Some(icon) = try_load(&project_icon) {
-            return Some(icon);
-        }
-    }
-
-    None
+/// 加载托盘图标
+///
+/// `name` 为相对于资源目录的路径，查找顺序见 [`IconResolver`]；
+/// 所有位置都失败时返回的错误中包含每个尝试过的路径。
+pub(crate) fn load_tray_icon(app: &AppHandle, name: &str) -> AppResult<RgbaImage> {
+    let scale_factor = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .map(|monitor| monitor.scale_factor())
+        .unwrap_or(1.0);
+    app.state::<IconResolver>()
+        .resolve(name, TRAY_ICON_SIZE, scale_factor)
 }
 
 /// 创建并设置系统托盘
-pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let main_i = MenuItem::with_id(app, "main", "主窗口", true, Some("CmdOrCtrl+M"))?;
-    let quit_i = MenuItem::with_id(app, "quit", "退出", true, Some("CmdOrCtrl+Q"))?;
-    let menu = Menu::with_items(app, &[&main_i, &quit_i])?;
-
-    // 尝试加载自定义托盘图标
-    // 可以指定图标路径，例如: "icons/32x32.png" 或 "icons/icon.png"
-    // 推荐使用 16x16 或 32x32 的 PNG 图标以获得最佳显示效果
-    let tray_icon = if let Some(custom_icon) = load_tray_icon(app, Some("icons/32x32.png")) {
-        custom_icon
-    } else {
-        // 如果加载失败，使用默认窗口图标
-        app.default_window_icon()
-            .ok_or("无法加载默认窗口图标")?
-            .clone()
-    };
-
-    let tray = TrayIconBuilder::new()
+pub fn setup_tray(app: &AppHandle) -> AppResult<()> {
+    let autostart_enabled = app.state::<SettingsStore>().get().autostart_enabled;
+    // 内置菜单项，前端注册的菜单项插入到“退出”之前
+    let items = vec![
+        TrayMenuItem::normal("main", "主窗口").with_accelerator("CmdOrCtrl+M"),
+        TrayMenuItem::check("autostart", "登录时启动", autostart_enabled),
+        TrayMenuItem::normal("quit", "退出").with_accelerator("CmdOrCtrl+Q"),
+    ];
+    let menu = build_menu(app, &items)?;
+    app.manage(TrayMenu::new(items));
+
+    // 彩色图标，内置图标保证总能加载
+    app.manage(IconResolver::for_app(app));
+    let colour = load_tray_icon(app, "icons/32x32.png")?;
+
+    // 按系统主题选择浅色、深色或模板图标，状态徽标在其上合成
+    let theme = app
+        .get_webview_window("main")
+        .and_then(|window| window.theme().ok())
+        .unwrap_or(Theme::Light);
+    let variant = IconVariant::for_theme(theme);
+    let base = load_variant(app, variant, &colour);
+    let tray_icon = Image::new_owned(base.as_raw().clone(), base.width(), base.height());
+    app.manage(TrayTheme::new(colour, variant));
+    app.manage(TrayIconState {
+        cache: Mutex::new(IconCache::new(base, variant.is_template())),
+        status: Mutex::new(TrayStatus::Idle),
+    });
+    app.manage(TrayAnimation::default());
+
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
         .on_tray_icon_event(handle_tray_icon_event)
-        .on_menu_event(handle_tray_menu_event)
+        .on_menu_event(|app, event| {
+            // 托盘菜单的点击发送 `tray-menu-clicked` 事件，内置菜单项再由 Rust 处理；
+            // 菜单事件是全局的，应用菜单的点击在这里忽略
+            if tray_menu::handle_click(app, event.id.as_ref()) {
+                handle_tray_menu_event(app, event);
+            }
+        })
         .menu(&menu)
         .show_menu_on_left_click(false)
         .icon(tray_icon)
+        .icon_as_template(variant.is_template())
         .build(app)?;
 
     Ok(())
 }
 
+/// 托盘图标的当前状态和各状态的合成结果
+pub struct TrayIconState {
+    cache: Mutex<IconCache>,
+    status: Mutex<TrayStatus>,
+}
+
+/// 切换托盘图标状态
+pub(crate) fn set_tray_status(app: &AppHandle, status: TrayStatus) -> AppResult<()> {
+    let state = app.state::<TrayIconState>();
+    *state.status.lock().unwrap() = status;
+    apply_tray_icon(app)
+}
+
+/// 更换基础图标（例如系统主题变化），清空已合成的状态图标
+pub(crate) fn set_tray_base_icon(
+    app: &AppHandle,
+    base: RgbaImage,
+    template: bool,
+) -> AppResult<()> {
+    let state = app.state::<TrayIconState>();
+    state.cache.lock().unwrap().set_base(base, template);
+    apply_tray_icon(app)
+}
+
+/// 当前的基础图标（不含状态徽标）及其是否为模板图标
+pub(crate) fn tray_base_icon(app: &AppHandle) -> Option<(RgbaImage, bool)> {
+    let state = app.try_state::<TrayIconState>()?;
+    let cache = state.cache.lock().unwrap();
+    Some((cache.base().clone(), cache.is_template()))
+}
+
+/// 按当前状态更新托盘图标，播放动画时由动画结束后恢复
+pub(crate) fn apply_tray_icon(app: &AppHandle) -> AppResult<()> {
+    let (Some(tray), Some(state)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayIconState>())
+    else {
+        return Ok(());
+    };
+    if app.state::<TrayAnimation>().is_running() {
+        return Ok(());
+    }
+    let status = *state.status.lock().unwrap();
+    let (icon, template) = {
+        let mut cache = state.cache.lock().unwrap();
+        let template = cache.is_template();
+        let icon = cache.get(status);
+        (
+            Image::new_owned(icon.as_raw().clone(), icon.width(), icon.height()),
+            template,
+        )
+    };
+    tray.set_icon(Some(icon))?;
+    // 彩色动画播放期间会关闭模板模式，恢复时一并还原
+    tray.set_icon_as_template(template)?;
+    Ok(())
+}
+
 /// 处理托盘图标事件
 fn handle_tray_icon_event(tray: &tauri::tray::TrayIcon, event: TrayIconEvent) {
-    match event {
-        TrayIconEvent::Click {
-            button: MouseButton::Left,
-            button_state: MouseButtonState::Up,
-            position,
-            ..
-        } => {
-            let app = tray.app_handle();
-
-            // 获取或创建 popover 窗口
-            let popover = if let Some(window) = app.get_webview_window("popover") {
-                window
-            } else {
-                // 如果窗口不存在，创建一个新的（这种情况不应该发生，因为配置中已经定义了）
-                return;
-            };
-
-            // 切换 popover 的显示状态
-            if let Ok(is_visible) = popover.is_visible() {
-                if is_visible {
-                    // 如果已经显示，则隐藏
-                    let _ = popover.hide();
-                } else {
-                    // 如果隐藏，则显示并定位到托盘图标下方
-                    show_popover_near_tray(&app, &popover, Some(position));
-                }
-            } else {
-                // 如果无法获取可见状态，直接显示
-                show_popover_near_tray(&app, &popover, Some(position));
-            }
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        position,
+        rect,
+        ..
+    } = event
+    {
+        toggle_popover(tray.app_handle(), Some(TrayAnchor { position, rect }));
+    }
+}
+
+/// 托盘图标点击时的位置和图标区域
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TrayAnchor {
+    /// 点击位置（物理像素），用于确定图标所在的显示器
+    pub position: PhysicalPosition<f64>,
+    /// 图标区域，按所在显示器的缩放比例换算为物理像素
+    pub rect: tauri::Rect,
+}
+
+/// 切换 popover 的显示状态
+///
+/// `tray` 为托盘图标点击信息，没有时显示在屏幕角落
+pub(crate) fn toggle_popover(app: &AppHandle, tray: Option<TrayAnchor>) {
+    // 获取或创建 popover 窗口
+    let popover = if let Some(window) = app.get_webview_window("popover") {
+        window
+    } else {
+        // 如果窗口不存在，创建一个新的（这种情况不应该发生，因为配置中已经定义了）
+        return;
+    };
+
+    // 切换 popover 的显示状态
+    if let Ok(is_visible) = popover.is_visible() {
+        if is_visible {
+            // 如果已经显示，则隐藏
+            let _ = popover.hide();
+        } else {
+            // 如果隐藏，则显示并定位到托盘图标下方
+            show_popover_near_tray(app, &popover, tray);
         }
-        _ => {}
+    } else {
+        // 如果无法获取可见状态，直接显示
+        show_popover_near_tray(app, &popover, tray);
     }
 }
 
-/// 在托盘图标下方显示 popover
+/// 在托盘图标旁显示 popover
+///
+/// 按托盘所在显示器的工作区判断任务栏位置（见 `placement`），没有托盘位置时显示在屏幕角落。
+/// 图标区域和间距都按该显示器的缩放比例换算，混合 DPI 的多显示器下也能对齐图标。
 fn show_popover_near_tray(
     app: &AppHandle,
     popover: &tauri::WebviewWindow,
-    tray_position: Option<tauri::PhysicalPosition<f64>>,
+    tray: Option<TrayAnchor>,
 ) {
-    // 获取 popover 窗口的尺寸
-    if let Ok(size) = popover.inner_size() {
-        let width = size.width as f64;
-        let height = size.height as f64;
-
-        // 如果有托盘位置信息，使用它来定位 popover
-        if let Some(tray_pos) = tray_position {
-            // 托盘图标的位置
-            let tray_x = tray_pos.x;
-            let tray_y = tray_pos.y;
-
-            // 计算 popover 的位置（在托盘图标下方，居中对齐）
-            // 在 macOS 上，托盘图标在顶部菜单栏，popover 显示在下方
-            #[cfg(target_os = "macos")]
-            {
-                // macOS: 托盘在顶部，popover 显示在托盘图标正下方
-                // 居中对齐：popover 的中心对齐到托盘图标的中心
-                let popover_x = tray_x - width / 2.0;
-                // 托盘图标通常在菜单栏中，高度约为 22px，所以 popover 应该在下方约 30px 处
-                let popover_y = tray_y + 30.0;
-                let _ = popover.set_position(tauri::PhysicalPosition::new(
-                    popover_x as i32,
-                    popover_y as i32,
-                ));
-            }
-
-            // 在其他平台上，也显示在托盘图标下方
-            #[cfg(not(target_os = "macos"))]
-            {
-                // 其他平台：托盘通常在任务栏，popover 显示在上方或下方
-                // 假设托盘在底部，popover 显示在上方
-                let popover_x = tray_x - width / 2.0;
-                let popover_y = tray_y - height - 10.0; // 托盘图标上方 10px
-                let _ = popover.set_position(tauri::PhysicalPosition::new(
-                    popover_x as i32,
-                    popover_y as i32,
-                ));
-            }
-        } else {
-            // 如果没有托盘位置信息，使用屏幕右上角（macOS）或右下角（其他平台）
-            if let Ok(Some(monitor)) = app.primary_monitor() {
-                let screen_size = monitor.size();
-                let screen_width = screen_size.width as f64;
-                let screen_height = screen_size.height as f64;
-
-                #[cfg(target_os = "macos")]
-                {
-                    // macOS: 显示在右上角下方
-                    let popover_x = screen_width - width - 20.0;
-                    let popover_y = 40.0;
-                    let _ = popover.set_position(tauri::PhysicalPosition::new(
-                        popover_x as i32,
-                        popover_y as i32,
-                    ));
-                }
-
-                #[cfg(not(target_os = "macos"))]
-                {
-                    // 其他平台: 显示在右下角
-                    let popover_x = screen_width - width - 20.0;
-                    let popover_y = screen_height - height - 20.0;
-                    let _ = popover.set_position(tauri::PhysicalPosition::new(
-                        popover_x as i32,
-                        popover_y as i32,
-                    ));
-                }
+    let monitor = tray
+        .and_then(|tray| {
+            app.monitor_from_point(tray.position.x, tray.position.y)
+                .ok()
+                .flatten()
+        })
+        .or_else(|| app.primary_monitor().ok().flatten());
+
+    if let (Some(monitor), Ok(size)) = (monitor, popover.inner_size()) {
+        let area = monitor.work_area();
+        let scale_factor = monitor.scale_factor();
+        let work_area = placement::Rect::new(
+            area.position.x as f64,
+            area.position.y as f64,
+            area.size.width as f64,
+            area.size.height as f64,
+        );
+        let popover_size = (size.width as f64, size.height as f64);
+        let target = match tray {
+            Some(tray) => {
+                let position = tray.rect.position.to_physical::<f64>(scale_factor);
+                let size = tray.rect.size.to_physical::<f64>(scale_factor);
+                place_popover(
+                    placement::Rect::new(position.x, position.y, size.width, size.height),
+                    work_area,
+                    scale_factor,
+                    popover_size,
+                )
             }
-        }
+            // macOS 菜单栏在顶部，其他平台默认任务栏在底部
+            None => place_in_corner(
+                work_area,
+                scale_factor,
+                popover_size,
+                if cfg!(target_os = "macos") {
+                    Edge::Top
+                } else {
+                    Edge::Bottom
+                },
+            ),
+        };
+        let _ = popover.set_position(PhysicalPosition::new(target.x, target.y));
     }
 
     // 显示并聚焦 popover
@@ -267,11 +308,21 @@ fn show_dock_icon_and_activate_with_app(_app_handle: &AppHandle) {
     }
 }
 
+#[cfg(not(target_os = "macos"))]
+fn show_dock_icon_and_activate_with_app(_app_handle: &AppHandle) {}
+
+/// 同步托盘菜单中“登录时启动”的勾选状态
+pub(crate) fn sync_autostart_item(app: &AppHandle, enabled: bool) {
+    if let Err(err) = tray_menu::set_checked(app, "autostart", enabled) {
+        log::error!("failed to update tray menu: {}", err);
+    }
+}
+
 /// 处理托盘菜单事件
-fn handle_tray_menu_event(app: &AppHandle, event: tauri::menu::MenuEvent) {
+pub(crate) fn handle_tray_menu_event(app: &AppHandle, event: tauri::menu::MenuEvent) {
     match event.id.as_ref() {
         "main" => {
-            println!("main menu item was clicked");
+            log::debug!("main menu item was clicked");
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
@@ -285,12 +336,21 @@ fn handle_tray_menu_event(app: &AppHandle, event: tauri::menu::MenuEvent) {
                 show_dock_icon();
             }
         }
+        "autostart" => {
+            // 点击时菜单项已切换勾选状态
+            let enabled = tray_menu::is_checked(app, "autostart");
+            if let Err(err) = set_autostart_enabled(app, enabled) {
+                log::error!("failed to toggle autostart: {}", err);
+                sync_autostart_item(app, !enabled);
+            }
+        }
         "quit" => {
-            println!("quit menu item was clicked");
+            log::info!("quit menu item was clicked");
+            window_state::save_all(app);
             app.exit(0);
         }
         _ => {
-            println!("menu item {:?} not handled", event.id);
+            log::debug!("menu item {:?} forwarded to frontend", event.id);
         }
     }
 }
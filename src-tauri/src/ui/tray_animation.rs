@@ -0,0 +1,362 @@
+use crate::core::error::{AppError, AppResult};
+use crate::ui::icon_resolver::IconResolver;
+use crate::ui::tray::{apply_tray_icon, tray_base_icon};
+use crate::ui::tray_icon::{draw_badge, Badge, Palette};
+use crate::ui::tray_menu::TRAY_ID;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{imageops, AnimationDecoder, ImageFormat, Rgba, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::{AppHandle, Manager};
+
+/// 默认帧率
+pub const DEFAULT_FPS: f64 = 10.0;
+
+/// 内置加载动画的帧数
+const SPINNER_FRAMES: usize = 8;
+
+/// 托盘动画参数
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TrayAnimationOptions {
+    /// 动画文件（GIF、APNG 或精灵图），相对于资源目录；不指定时使用内置的加载动画
+    pub source: Option<String>,
+    /// 精灵图每帧的宽度，与 `frame_height` 同时指定时按从左到右、从上到下切分
+    pub frame_width: Option<u32>,
+    /// 精灵图每帧的高度
+    pub frame_height: Option<u32>,
+    /// 帧率，限制在 1 到 60 之间
+    pub fps: f64,
+}
+
+impl Default for TrayAnimationOptions {
+    fn default() -> Self {
+        Self {
+            source: None,
+            frame_width: None,
+            frame_height: None,
+            fps: DEFAULT_FPS,
+        }
+    }
+}
+
+/// 解码动画帧：GIF、APNG 取所有帧，其他格式作为单帧
+pub fn decode_frames(bytes: &[u8]) -> AppResult<Vec<RgbaImage>> {
+    let invalid = |err: image::ImageError| AppError::InvalidArgument(err.to_string());
+    let frames = match image::guess_format(bytes).map_err(invalid)? {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))
+            .and_then(|decoder| decoder.into_frames().collect_frames())
+            .map_err(invalid)?,
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(bytes)).map_err(invalid)?;
+            if decoder.is_apng().map_err(invalid)? {
+                decoder
+                    .apng()
+                    .and_then(|apng| apng.into_frames().collect_frames())
+                    .map_err(invalid)?
+            } else {
+                return Ok(vec![image::load_from_memory(bytes)
+                    .map_err(invalid)?
+                    .to_rgba8()]);
+            }
+        }
+        _ => {
+            return Ok(vec![image::load_from_memory(bytes)
+                .map_err(invalid)?
+                .to_rgba8()])
+        }
+    };
+    Ok(frames
+        .into_iter()
+        .map(|frame| frame.into_buffer())
+        .collect())
+}
+
+/// 按帧尺寸切分精灵图，从左到右、从上到下，不足一帧的边缘忽略
+pub fn split_sprite_sheet(sheet: &RgbaImage, width: u32, height: u32) -> AppResult<Vec<RgbaImage>> {
+    if width == 0 || height == 0 || width > sheet.width() || height > sheet.height() {
+        return Err(AppError::InvalidArgument(format!(
+            "invalid frame size {}x{} for {}x{} sprite sheet",
+            width,
+            height,
+            sheet.width(),
+            sheet.height()
+        )));
+    }
+    let mut frames = Vec::new();
+    for y in (0..=sheet.height() - height).step_by(height as usize) {
+        for x in (0..=sheet.width() - width).step_by(width as usize) {
+            frames.push(imageops::crop_imm(sheet, x, y, width, height).to_image());
+        }
+    }
+    Ok(frames)
+}
+
+/// 内置加载动画：在徽标上绕圈的亮点，后面跟着逐渐变暗的拖尾
+///
+/// 模板图标（`template`）中亮点镂空为透明，拖尾逐渐恢复为不透明。
+pub fn spinner_frames(base: &RgbaImage, count: usize, template: bool) -> Vec<RgbaImage> {
+    let badge = Badge::for_size(base.width(), base.height());
+    let palette = Palette::new(template);
+    let orbit = badge.radius * 0.55;
+    let dot = (badge.radius * 0.3).max(1.0);
+    (0..count)
+        .map(|frame| {
+            let mut icon = base.clone();
+            draw_badge(&mut icon, badge, palette.busy);
+            for step in 0..count {
+                let angle = std::f64::consts::TAU * step as f64 / count as f64;
+                // 当前帧的点最亮，之前的点逐渐淡出
+                let age = (frame + count - step) % count;
+                let alpha = 0xFF - (0xFF * age / count) as u8;
+                let cx = badge.cx + orbit * angle.sin();
+                let cy = badge.cy - orbit * angle.cos();
+                if template {
+                    cut_dot(&mut icon, cx, cy, dot, alpha);
+                } else {
+                    fill_dot(&mut icon, cx, cy, dot, Rgba([0xFF, 0xFF, 0xFF, alpha]));
+                }
+            }
+            icon
+        })
+        .collect()
+}
+
+/// 按透明度把圆点混合到图标上
+fn fill_dot(icon: &mut RgbaImage, cx: f64, cy: f64, radius: f64, color: Rgba<u8>) {
+    let alpha = color[3] as u32;
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let dx = x as f64 + 0.5 - cx;
+        let dy = y as f64 + 0.5 - cy;
+        if dx * dx + dy * dy > radius * radius {
+            continue;
+        }
+        for channel in 0..3 {
+            pixel[channel] = ((color[channel] as u32 * alpha
+                + pixel[channel] as u32 * (255 - alpha))
+                / 255) as u8;
+        }
+        pixel[3] = pixel[3].max(color[3]);
+    }
+}
+
+/// 按 `alpha` 把圆点区域镂空，用于模板图标
+fn cut_dot(icon: &mut RgbaImage, cx: f64, cy: f64, radius: f64, alpha: u8) {
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let dx = x as f64 + 0.5 - cx;
+        let dy = y as f64 + 0.5 - cy;
+        if dx * dx + dy * dy <= radius * radius {
+            pixel[3] = pixel[3].min(0xFF - alpha);
+        }
+    }
+}
+
+/// 托盘动画状态
+///
+/// 动画线程只负责计时，每一帧都投递到主线程设置图标；
+/// 每次开始或停止都会递增 `generation`，过期的帧在主线程上被丢弃，
+/// 因此停止后恢复的图标不会被残留的帧覆盖。
+#[derive(Default)]
+pub struct TrayAnimation {
+    generation: AtomicU64,
+    running: AtomicBool,
+}
+
+impl TrayAnimation {
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+/// 按参数加载帧并开始播放，已有动画会被替换
+///
+/// 动画文件是彩色的，播放期间关闭模板图标；内置加载动画跟随基础图标。
+pub fn start_animation(app: &AppHandle, options: &TrayAnimationOptions) -> AppResult<()> {
+    let (frames, template) = match &options.source {
+        Some(source) => {
+            let bytes = app.state::<IconResolver>().read(source)?;
+            match (options.frame_width, options.frame_height) {
+                (Some(width), Some(height)) => {
+                    let sheet = image::load_from_memory(&bytes)
+                        .map_err(|err| AppError::InvalidArgument(err.to_string()))?
+                        .to_rgba8();
+                    (split_sprite_sheet(&sheet, width, height)?, false)
+                }
+                _ => (decode_frames(&bytes)?, false),
+            }
+        }
+        None => {
+            let (base, template) = tray_base_icon(app)
+                .ok_or_else(|| AppError::NotFound("tray icon is not ready".into()))?;
+            (spinner_frames(&base, SPINNER_FRAMES, template), template)
+        }
+    };
+    play(app, frames, options.fps, template)
+}
+
+/// 循环播放帧序列，`template` 为帧是否作为 macOS 模板图标显示
+pub fn play(app: &AppHandle, frames: Vec<RgbaImage>, fps: f64, template: bool) -> AppResult<()> {
+    if frames.is_empty() {
+        return Err(AppError::InvalidArgument("animation has no frames".into()));
+    }
+    let fps = if fps.is_finite() { fps } else { DEFAULT_FPS };
+    let interval = Duration::from_secs_f64(1.0 / fps.clamp(1.0, 60.0));
+    let frames: Arc<Vec<Image<'static>>> = Arc::new(
+        frames
+            .into_iter()
+            .map(|frame| {
+                let (width, height) = frame.dimensions();
+                Image::new_owned(frame.into_raw(), width, height)
+            })
+            .collect(),
+    );
+
+    let animation = app.state::<TrayAnimation>();
+    let generation = animation.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    animation.running.store(true, Ordering::SeqCst);
+    log::debug!(
+        "tray animation started ({} frames, {:?}/frame)",
+        frames.len(),
+        interval
+    );
+
+    // 排在第一帧之前；停止后由 `apply_tray_icon` 恢复
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        if let Some(tray) = handle.tray_by_id(TRAY_ID) {
+            let _ = tray.set_icon_as_template(template);
+        }
+    })?;
+
+    let app = app.clone();
+    thread::spawn(move || {
+        for index in (0..frames.len()).cycle() {
+            if !app.state::<TrayAnimation>().is_current(generation) {
+                break;
+            }
+            let handle = app.clone();
+            let frames = frames.clone();
+            let posted = app.run_on_main_thread(move || {
+                if !handle.state::<TrayAnimation>().is_current(generation) {
+                    return;
+                }
+                if let Some(tray) = handle.tray_by_id(TRAY_ID) {
+                    let _ = tray.set_icon(Some(frames[index].clone()));
+                }
+            });
+            if posted.is_err() {
+                break;
+            }
+            thread::sleep(interval);
+        }
+    });
+    Ok(())
+}
+
+/// 停止动画并恢复为当前状态的图标
+pub fn stop_animation(app: &AppHandle) -> AppResult<()> {
+    let animation = app.state::<TrayAnimation>();
+    if !animation.running.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    animation.generation.fetch_add(1, Ordering::SeqCst);
+    log::debug!("tray animation stopped");
+
+    // 在主线程恢复，排在已投递的帧之后
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        if let Err(err) = apply_tray_icon(&handle) {
+            log::error!("failed to restore tray icon: {}", err);
+        }
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::Frame;
+
+    fn solid(width: u32, height: u32, value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([value, value, value, 0xFF]))
+    }
+
+    #[test]
+    fn decodes_gif_frames() {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            let frames = [0x00, 0x80, 0xFF].map(|value| Frame::new(solid(4, 4, value)));
+            encoder.encode_frames(frames).unwrap();
+        }
+        let frames = decode_frames(&bytes).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1].get_pixel(0, 0).0, [0x80, 0x80, 0x80, 0xFF]);
+    }
+
+    #[test]
+    fn decodes_still_images_as_one_frame() {
+        let mut bytes = Vec::new();
+        solid(4, 4, 0x40)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        let frames = decode_frames(&bytes).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert!(decode_frames(b"not an image").is_err());
+    }
+
+    #[test]
+    fn splits_sprite_sheets_row_major() {
+        let mut sheet = solid(11, 5, 0);
+        // 2x2 帧，第二行第一帧标记为白色；最右一列和最下一行不足一帧，忽略
+        for (x, y) in [(0, 2), (1, 2), (0, 3), (1, 3)] {
+            sheet.put_pixel(x, y, Rgba([0xFF; 4]));
+        }
+        let frames = split_sprite_sheet(&sheet, 2, 2).unwrap();
+        assert_eq!(frames.len(), 10);
+        assert_eq!(frames[5].get_pixel(0, 0).0, [0xFF; 4]);
+        assert_eq!(frames[0].get_pixel(0, 0).0, [0, 0, 0, 0xFF]);
+        assert!(frames.iter().all(|frame| frame.dimensions() == (2, 2)));
+        assert!(split_sprite_sheet(&sheet, 0, 2).is_err());
+        assert!(split_sprite_sheet(&sheet, 12, 2).is_err());
+    }
+
+    #[test]
+    fn spinner_frames_differ_and_keep_size() {
+        for template in [false, true] {
+            let frames = spinner_frames(&solid(32, 32, 0x20), SPINNER_FRAMES, template);
+            assert_eq!(frames.len(), SPINNER_FRAMES);
+            for (index, frame) in frames.iter().enumerate() {
+                assert_eq!(frame.dimensions(), (32, 32));
+                assert_ne!(frame, &frames[(index + 1) % SPINNER_FRAMES]);
+            }
+        }
+    }
+
+    #[test]
+    fn template_spinner_cuts_dots_out() {
+        let frames = spinner_frames(&solid(32, 32, 0x00), SPINNER_FRAMES, true);
+        let badge = Badge::for_size(32, 32);
+        // 第一帧的亮点在徽标正上方
+        let (x, y) = (badge.cx as u32, (badge.cy - badge.radius * 0.55) as u32);
+        assert_eq!(frames[0].get_pixel(x, y)[3], 0);
+        // 只有透明度变化，不会出现白色或彩色像素
+        for frame in &frames {
+            assert!(frame
+                .pixels()
+                .all(|pixel| pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0));
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! 托盘图标状态合成
+//!
+//! 在基础图标（RGBA）上绘制状态徽标：圆点、未读数字或叠加符号。
+//! 纯图像处理，不依赖 Tauri；合成结果按状态缓存。
+//! macOS 模板图标只保留透明度，徽标画成不透明的实心圆，字形镂空为透明。
+//! 另外提供由彩色图标生成单色图标的 `monochrome`（见 `tray_theme`）。
+
+use image::{Rgba, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 托盘图标状态（前端 `set_tray_status` 传入）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayState {
+    #[default]
+    Idle,
+    Busy,
+    Error,
+    Unread,
+    Paused,
+}
+
+/// 托盘图标状态，未读状态带未读数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TrayStatus {
+    #[default]
+    Idle,
+    Busy,
+    Error,
+    Unread(u32),
+    Paused,
+}
+
+impl TrayStatus {
+    pub fn new(state: TrayState, count: u32) -> Self {
+        match state {
+            TrayState::Idle => Self::Idle,
+            TrayState::Busy => Self::Busy,
+            TrayState::Error => Self::Error,
+            // 没有未读时显示为空闲，数字徽标最多显示 `9+`，超过 9 的未读数共用同一个缓存
+            TrayState::Unread if count == 0 => Self::Idle,
+            TrayState::Unread => Self::Unread(count.min(10)),
+            TrayState::Paused => Self::Paused,
+        }
+    }
+}
+
+const BUSY: Rgba<u8> = Rgba([0xF5, 0xA6, 0x23, 0xFF]);
+const ALERT: Rgba<u8> = Rgba([0xE5, 0x39, 0x35, 0xFF]);
+const MUTED: Rgba<u8> = Rgba([0x75, 0x75, 0x75, 0xFF]);
+const GLYPH: Rgba<u8> = Rgba([0xFF, 0xFF, 0xFF, 0xFF]);
+const TEMPLATE: Rgba<u8> = Rgba([0x00, 0x00, 0x00, 0xFF]);
+const CUTOUT: Rgba<u8> = Rgba([0x00, 0x00, 0x00, 0x00]);
+
+/// 徽标和字形的颜色
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub busy: Rgba<u8>,
+    pub alert: Rgba<u8>,
+    pub muted: Rgba<u8>,
+    pub glyph: Rgba<u8>,
+}
+
+impl Palette {
+    /// 模板图标由系统按菜单栏着色，颜色会被忽略，只能靠透明度区分徽标和字形
+    pub fn new(template: bool) -> Self {
+        if template {
+            Self {
+                busy: TEMPLATE,
+                alert: TEMPLATE,
+                muted: TEMPLATE,
+                glyph: CUTOUT,
+            }
+        } else {
+            Self {
+                busy: BUSY,
+                alert: ALERT,
+                muted: MUTED,
+                glyph: GLYPH,
+            }
+        }
+    }
+}
+
+/// 3x5 点阵字形，`#` 为填充
+type Glyph = [&'static str; 5];
+
+const DIGITS: [Glyph; 10] = [
+    ["###", "#.#", "#.#", "#.#", "###"],
+    [".#.", "##.", ".#.", ".#.", "###"],
+    ["###", "..#", "###", "#..", "###"],
+    ["###", "..#", "###", "..#", "###"],
+    ["#.#", "#.#", "###", "..#", "..#"],
+    ["###", "#..", "###", "..#", "###"],
+    ["###", "#..", "###", "#.#", "###"],
+    ["###", "..#", ".#.", ".#.", ".#."],
+    ["###", "#.#", "###", "#.#", "###"],
+    ["###", "#.#", "###", "..#", "###"],
+];
+const PLUS: Glyph = ["...", ".#.", "###", ".#.", "..."];
+const EXCLAMATION: Glyph = [".#.", ".#.", ".#.", "...", ".#."];
+const PAUSE: Glyph = ["#.#", "#.#", "#.#", "#.#", "#.#"];
+
+/// 徽标位置和大小（像素）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Badge {
+    pub cx: f64,
+    pub cy: f64,
+    pub radius: f64,
+}
+
+impl Badge {
+    /// 右下角的徽标，直径约为图标边长的 0.6
+    pub fn for_size(width: u32, height: u32) -> Self {
+        let radius = (width.min(height) as f64 * 0.3).max(3.0);
+        Self {
+            cx: width as f64 - radius,
+            cy: height as f64 - radius,
+            radius,
+        }
+    }
+
+    /// 徽标周围镂空的宽度，让徽标与图标内容分开
+    fn ring(&self) -> f64 {
+        (self.radius / 4.0).max(1.0)
+    }
+
+    fn distance(&self, x: u32, y: u32) -> f64 {
+        let dx = x as f64 + 0.5 - self.cx;
+        let dy = y as f64 + 0.5 - self.cy;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// 按状态合成托盘图标，`template` 为基础图标是否作为 macOS 模板图标显示
+pub fn render(base: &RgbaImage, status: TrayStatus, template: bool) -> RgbaImage {
+    let mut icon = base.clone();
+    let badge = Badge::for_size(icon.width(), icon.height());
+    let palette = Palette::new(template);
+    match status {
+        TrayStatus::Idle => {}
+        TrayStatus::Busy => draw_badge(&mut icon, badge, palette.busy),
+        TrayStatus::Error => {
+            draw_badge(&mut icon, badge, palette.alert);
+            draw_text(&mut icon, badge, &[EXCLAMATION], palette.glyph);
+        }
+        TrayStatus::Unread(count) => {
+            draw_badge(&mut icon, badge, palette.alert);
+            draw_text(&mut icon, badge, &count_glyphs(count), palette.glyph);
+        }
+        TrayStatus::Paused => {
+            desaturate(&mut icon);
+            draw_badge(&mut icon, badge, palette.muted);
+            draw_text(&mut icon, badge, &[PAUSE], palette.glyph);
+        }
+    }
+    icon
+}
+
+fn count_glyphs(count: u32) -> Vec<Glyph> {
+    if count > 9 {
+        vec![DIGITS[9], PLUS]
+    } else {
+        vec![DIGITS[count as usize]]
+    }
+}
+
+/// 绘制实心圆徽标，并把周围一圈镂空为透明
+pub fn draw_badge(icon: &mut RgbaImage, badge: Badge, color: Rgba<u8>) {
+    let outer = badge.radius + badge.ring();
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let distance = badge.distance(x, y);
+        if distance <= badge.radius {
+            *pixel = color;
+        } else if distance <= outer {
+            pixel[3] = 0;
+        }
+    }
+}
+
+/// 在徽标中央绘制点阵字形，字形之间留一列空白；放不下时缩小到 1 倍
+fn draw_text(icon: &mut RgbaImage, badge: Badge, glyphs: &[Glyph], color: Rgba<u8>) {
+    let columns = glyphs.len() as u32 * 4 - 1;
+    // 文字高度约为徽标直径的 0.6
+    let scale = ((badge.radius * 1.2 / 5.0).floor() as u32)
+        .min((badge.radius * 1.8 / columns as f64).floor() as u32)
+        .max(1);
+    let left = (badge.cx - (columns * scale) as f64 / 2.0).round() as i64;
+    let top = (badge.cy - (5 * scale) as f64 / 2.0).round() as i64;
+
+    for (index, glyph) in glyphs.iter().enumerate() {
+        let glyph_left = left + (index as u32 * 4 * scale) as i64;
+        for (row, line) in glyph.iter().enumerate() {
+            for (column, cell) in line.chars().enumerate() {
+                if cell != '#' {
+                    continue;
+                }
+                let x0 = glyph_left + (column as u32 * scale) as i64;
+                let y0 = top + (row as u32 * scale) as i64;
+                fill_rect(icon, x0, y0, scale, color);
+            }
+        }
+    }
+}
+
+fn fill_rect(icon: &mut RgbaImage, x0: i64, y0: i64, size: u32, color: Rgba<u8>) {
+    for y in y0..y0 + size as i64 {
+        for x in x0..x0 + size as i64 {
+            if x >= 0 && y >= 0 && (x as u32) < icon.width() && (y as u32) < icon.height() {
+                icon.put_pixel(x as u32, y as u32, color);
+            }
+        }
+    }
+}
+
+/// 转为灰度，保留透明度
+pub fn desaturate(icon: &mut RgbaImage) {
+    for pixel in icon.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let luma = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8;
+        *pixel = Rgba([luma, luma, luma, a]);
+    }
+}
+
+/// 由彩色图标生成单色图标：颜色统一为 `color`，形状由透明度决定
+///
+/// 图标有透明区域时沿用原透明度；完全不透明时以左上角像素为背景色，
+/// 按与背景色的差异生成透明度。
+pub fn monochrome(icon: &RgbaImage, color: Rgba<u8>) -> RgbaImage {
+    let (width, height) = icon.dimensions();
+    if width == 0 || height == 0 {
+        return icon.clone();
+    }
+    let opaque = icon.pixels().all(|pixel| pixel[3] == 0xFF);
+    let background = *icon.get_pixel(0, 0);
+    let contrast = |pixel: &Rgba<u8>| {
+        (0..3)
+            .map(|channel| pixel[channel].abs_diff(background[channel]))
+            .max()
+            .unwrap_or(0)
+    };
+    // 拉伸到完整的透明度范围
+    let max_contrast = icon.pixels().map(contrast).max().unwrap_or(0).max(1) as u32;
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let pixel = icon.get_pixel(x, y);
+        let alpha = if opaque {
+            (contrast(pixel) as u32 * 0xFF / max_contrast) as u8
+        } else {
+            pixel[3]
+        };
+        Rgba([color[0], color[1], color[2], alpha])
+    })
+}
+
+/// 按状态缓存的合成结果，更换基础图标时清空
+pub struct IconCache {
+    base: RgbaImage,
+    template: bool,
+    variants: HashMap<TrayStatus, RgbaImage>,
+}
+
+impl IconCache {
+    pub fn new(base: RgbaImage, template: bool) -> Self {
+        Self {
+            base,
+            template,
+            variants: HashMap::new(),
+        }
+    }
+
+    pub fn base(&self) -> &RgbaImage {
+        &self.base
+    }
+
+    /// 基础图标是否为模板图标
+    pub fn is_template(&self) -> bool {
+        self.template
+    }
+
+    /// 更换基础图标
+    pub fn set_base(&mut self, base: RgbaImage, template: bool) {
+        self.base = base;
+        self.template = template;
+        self.variants.clear();
+    }
+
+    /// 获取状态对应的图标，没有缓存时合成
+    pub fn get(&mut self, status: TrayStatus) -> &RgbaImage {
+        let (base, template) = (&self.base, self.template);
+        self.variants
+            .entry(status)
+            .or_insert_with(|| render(base, status, template))
+    }
+
+    /// 已缓存的状态数
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.variants.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: Rgba<u8> = Rgba([0x20, 0x60, 0xC0, 0xFF]);
+
+    fn base() -> RgbaImage {
+        RgbaImage::from_pixel(32, 32, BASE)
+    }
+
+    fn count(icon: &RgbaImage, color: Rgba<u8>) -> usize {
+        icon.pixels().filter(|pixel| **pixel == color).count()
+    }
+
+    #[test]
+    fn idle_keeps_base_icon() {
+        assert_eq!(render(&base(), TrayStatus::Idle, false), base());
+        assert_eq!(TrayStatus::new(TrayState::Unread, 0), TrayStatus::Idle);
+    }
+
+    #[test]
+    fn busy_draws_badge_in_bottom_right() {
+        let icon = render(&base(), TrayStatus::Busy, false);
+        let badge = Badge::for_size(32, 32);
+        assert_eq!(*icon.get_pixel(badge.cx as u32, badge.cy as u32), BUSY);
+        assert_eq!(*icon.get_pixel(31, badge.cy as u32), BUSY);
+        // 左上角不受影响
+        assert_eq!(*icon.get_pixel(0, 0), BASE);
+        assert_eq!(*icon.get_pixel(8, 8), BASE);
+    }
+
+    #[test]
+    fn clears_ring_around_badge() {
+        let icon = render(&base(), TrayStatus::Busy, false);
+        let badge = Badge::for_size(32, 32);
+        // 徽标左侧一圈透明
+        let x = (badge.cx - badge.radius - badge.ring() / 2.0) as u32;
+        assert_eq!(icon.get_pixel(x, badge.cy as u32)[3], 0);
+        let x = (badge.cx - badge.radius - badge.ring() - 1.0) as u32;
+        assert_eq!(*icon.get_pixel(x, badge.cy as u32), BASE);
+    }
+
+    #[test]
+    fn unread_draws_digits_inside_badge() {
+        let one = render(&base(), TrayStatus::Unread(1), false);
+        let eight = render(&base(), TrayStatus::Unread(8), false);
+        let many = render(&base(), TrayStatus::Unread(10), false);
+        assert!(count(&one, GLYPH) > 0);
+        assert!(count(&eight, GLYPH) > count(&one, GLYPH));
+        assert_ne!(many, render(&base(), TrayStatus::Unread(9), false));
+
+        // 白色像素都在徽标内
+        let badge = Badge::for_size(32, 32);
+        for (x, y, pixel) in many.enumerate_pixels() {
+            if *pixel == GLYPH {
+                assert!(badge.distance(x, y) <= badge.radius, "({}, {})", x, y);
+            }
+        }
+        assert_eq!(
+            TrayStatus::new(TrayState::Unread, 120),
+            TrayStatus::Unread(10)
+        );
+    }
+
+    #[test]
+    fn error_and_paused_use_distinct_glyphs() {
+        let error = render(&base(), TrayStatus::Error, false);
+        let paused = render(&base(), TrayStatus::Paused, false);
+        assert!(count(&error, ALERT) > 0);
+        assert!(count(&error, GLYPH) > 0);
+        assert!(count(&paused, MUTED) > 0);
+        assert!(count(&paused, GLYPH) > count(&error, GLYPH));
+    }
+
+    #[test]
+    fn paused_desaturates_and_keeps_alpha() {
+        let mut base = base();
+        base.put_pixel(0, 1, Rgba([0xFF, 0x00, 0x00, 0x80]));
+        let icon = render(&base, TrayStatus::Paused, false);
+        let [r, g, b, a] = icon.get_pixel(0, 0).0;
+        assert!(r == g && g == b);
+        assert_eq!(a, 0xFF);
+        assert_eq!(icon.get_pixel(0, 1).0, [76, 76, 76, 0x80]);
+    }
+
+    #[test]
+    fn works_on_tiny_icons() {
+        let base = RgbaImage::from_pixel(8, 8, BASE);
+        for status in [
+            TrayStatus::Error,
+            TrayStatus::Unread(10),
+            TrayStatus::Paused,
+        ] {
+            let icon = render(&base, status, false);
+            assert_eq!(icon.dimensions(), (8, 8));
+        }
+    }
+
+    #[test]
+    fn monochrome_keeps_alpha_of_transparent_icons() {
+        let mut icon = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        icon.put_pixel(1, 1, Rgba([0xE5, 0x39, 0x35, 0xFF]));
+        icon.put_pixel(2, 2, Rgba([0x20, 0x60, 0xC0, 0x80]));
+        let mono = monochrome(&icon, Rgba([0xFF, 0xFF, 0xFF, 0xFF]));
+        assert_eq!(mono.get_pixel(0, 0).0, [0xFF, 0xFF, 0xFF, 0]);
+        assert_eq!(mono.get_pixel(1, 1).0, [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(mono.get_pixel(2, 2).0, [0xFF, 0xFF, 0xFF, 0x80]);
+    }
+
+    #[test]
+    fn monochrome_derives_alpha_from_opaque_background() {
+        let mut icon = RgbaImage::from_pixel(4, 4, Rgba([0xFF, 0xFF, 0xFF, 0xFF]));
+        icon.put_pixel(1, 1, Rgba([0x00, 0x00, 0x00, 0xFF]));
+        icon.put_pixel(2, 2, Rgba([0x80, 0xFF, 0xFF, 0xFF]));
+        let mono = monochrome(&icon, Rgba([0, 0, 0, 0xFF]));
+        assert_eq!(mono.get_pixel(0, 0)[3], 0);
+        assert_eq!(mono.get_pixel(1, 1)[3], 0xFF);
+        assert_eq!(mono.get_pixel(2, 2)[3], 0x7F);
+        assert!(mono
+            .pixels()
+            .all(|pixel| pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0));
+    }
+
+    #[test]
+    fn caches_variants_until_base_changes() {
+        let mut cache = IconCache::new(base(), false);
+        let first = cache.get(TrayStatus::Busy).clone();
+        assert_eq!(cache.get(TrayStatus::Busy), &first);
+        cache.get(TrayStatus::Unread(3));
+        assert_eq!(cache.len(), 2);
+
+        cache.set_base(RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 0xFF])), false);
+        assert_eq!(cache.len(), 0);
+        assert_ne!(cache.get(TrayStatus::Busy), &first);
+
+        cache.set_base(base(), true);
+        assert!(cache.is_template());
+        assert_eq!(
+            *cache.get(TrayStatus::Busy),
+            render(&base(), TrayStatus::Busy, true)
+        );
+    }
+
+    #[test]
+    fn template_badges_cut_glyphs_out() {
+        let base = RgbaImage::from_pixel(32, 32, TEMPLATE);
+        let badge = Badge::for_size(32, 32);
+        for status in [TrayStatus::Error, TrayStatus::Unread(8), TrayStatus::Paused] {
+            let icon = render(&base, status, true);
+            // 模板图标只看透明度：不能出现彩色或白色，字形是徽标内的透明像素
+            assert!(icon
+                .pixels()
+                .all(|pixel| pixel[3] == 0 || pixel.0 == TEMPLATE.0));
+            let cut = icon
+                .enumerate_pixels()
+                .filter(|(x, y, pixel)| badge.distance(*x, *y) <= badge.radius && pixel[3] == 0)
+                .count();
+            assert!(cut > 0, "{:?}", status);
+        }
+        let busy = render(&base, TrayStatus::Busy, true);
+        assert_eq!(*busy.get_pixel(badge.cx as u32, badge.cy as u32), TEMPLATE);
+    }
+}
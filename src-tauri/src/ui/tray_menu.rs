@@ -0,0 +1,534 @@
+use crate::core::error::{AppError, AppResult};
+use crate::core::events::{EventEmitter, TrayMenuClickedPayload};
+use crate::ui::menu::APP_MENU_ITEMS;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::menu::{
+    CheckMenuItem, IsMenuItem, Menu, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu,
+};
+use tauri::{AppHandle, Manager, Wry};
+
+/// 托盘图标 id
+pub const TRAY_ID: &str = "main";
+
+/// 内置菜单项，由 Rust 处理，前端不能修改或删除
+pub const BUILTIN_ITEMS: &[&str] = &["main", "autostart", "quit"];
+
+/// 菜单项类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayMenuItemKind {
+    #[default]
+    Normal,
+    Check,
+    Submenu,
+    Separator,
+}
+
+/// 托盘菜单项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TrayMenuItem {
+    /// 唯一 id，点击时随 `tray-menu-clicked` 事件发送
+    pub id: String,
+    #[serde(default)]
+    pub kind: TrayMenuItemKind,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// 勾选状态（仅 `check`）
+    #[serde(default)]
+    pub checked: bool,
+    /// 快捷键，例如 `CmdOrCtrl+Q`
+    #[serde(default)]
+    pub accelerator: Option<String>,
+    /// 子菜单项（仅 `submenu`）
+    #[serde(default)]
+    pub items: Vec<TrayMenuItem>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Default for TrayMenuItem {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            kind: TrayMenuItemKind::Normal,
+            label: String::new(),
+            enabled: enabled_by_default(),
+            checked: false,
+            accelerator: None,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl TrayMenuItem {
+    pub fn normal(id: &str, label: &str) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn check(id: &str, label: &str, checked: bool) -> Self {
+        Self {
+            kind: TrayMenuItemKind::Check,
+            checked,
+            ..Self::normal(id, label)
+        }
+    }
+
+    pub fn submenu(id: &str, label: &str, items: Vec<TrayMenuItem>) -> Self {
+        Self {
+            kind: TrayMenuItemKind::Submenu,
+            items,
+            ..Self::normal(id, label)
+        }
+    }
+
+    pub fn separator(id: &str) -> Self {
+        Self {
+            id: id.into(),
+            kind: TrayMenuItemKind::Separator,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_accelerator(mut self, accelerator: &str) -> Self {
+        self.accelerator = Some(accelerator.into());
+        self
+    }
+}
+
+/// 托盘菜单模型
+///
+/// 菜单的唯一数据来源：修改后调用 `rebuild` 重新生成原生菜单。
+/// 前端通过 `add_item`、`remove_item`、`update_item` 修改，内置项只能由 Rust 修改。
+pub struct TrayMenu {
+    items: Mutex<Vec<TrayMenuItem>>,
+}
+
+impl TrayMenu {
+    pub fn new(items: Vec<TrayMenuItem>) -> Self {
+        Self {
+            items: Mutex::new(items),
+        }
+    }
+
+    /// 当前菜单项的副本
+    pub fn items(&self) -> Vec<TrayMenuItem> {
+        self.items.lock().unwrap().clone()
+    }
+
+    /// 按 id 查找菜单项（包括子菜单项）
+    pub fn get(&self, id: &str) -> Option<TrayMenuItem> {
+        find(&self.items.lock().unwrap(), id).cloned()
+    }
+
+    /// 插入菜单项
+    ///
+    /// `parent` 为子菜单 id，不指定时插入顶层；`before` 为插入位置，不指定时追加到末尾。
+    pub fn add(
+        &self,
+        item: TrayMenuItem,
+        parent: Option<&str>,
+        before: Option<&str>,
+    ) -> AppResult<()> {
+        let mut items = self.items.lock().unwrap();
+        validate(&item)?;
+        if let Some(id) = first_duplicate(&items, &item) {
+            return Err(AppError::InvalidArgument(format!(
+                "menu item `{}` already exists",
+                id
+            )));
+        }
+
+        let list = match parent {
+            Some(parent) => {
+                let parent = find_mut(&mut items, parent).ok_or_else(|| not_found(parent))?;
+                if parent.kind != TrayMenuItemKind::Submenu {
+                    return Err(AppError::InvalidArgument(format!(
+                        "menu item `{}` is not a submenu",
+                        parent.id
+                    )));
+                }
+                &mut parent.items
+            }
+            None => &mut *items,
+        };
+        let index = match before {
+            Some(before) => list
+                .iter()
+                .position(|item| item.id == before)
+                .ok_or_else(|| not_found(before))?,
+            None => list.len(),
+        };
+        list.insert(index, item);
+        Ok(())
+    }
+
+    /// 删除菜单项（包括其子菜单项）
+    pub fn remove(&self, id: &str) -> AppResult<TrayMenuItem> {
+        remove_from(&mut self.items.lock().unwrap(), id).ok_or_else(|| not_found(id))
+    }
+
+    /// 修改菜单项，返回修改后的副本
+    pub fn update<F>(&self, id: &str, f: F) -> AppResult<TrayMenuItem>
+    where
+        F: FnOnce(&mut TrayMenuItem),
+    {
+        let mut items = self.items.lock().unwrap();
+        let item = find_mut(&mut items, id).ok_or_else(|| not_found(id))?;
+        f(item);
+        Ok(item.clone())
+    }
+
+    /// 记录点击：勾选项切换勾选状态，返回要发送的事件负载
+    ///
+    /// 不在模型中的 id（例如应用菜单的点击）返回 `None`。
+    fn click(&self, id: &str) -> Option<TrayMenuClickedPayload> {
+        let mut items = self.items.lock().unwrap();
+        let item = find_mut(&mut items, id)?;
+        let checked = (item.kind == TrayMenuItemKind::Check).then(|| {
+            item.checked = !item.checked;
+            item.checked
+        });
+        Some(TrayMenuClickedPayload {
+            id: id.to_string(),
+            checked,
+        })
+    }
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("menu item `{}` not found", id))
+}
+
+fn validate(item: &TrayMenuItem) -> AppResult<()> {
+    if item.id.is_empty() {
+        return Err(AppError::InvalidArgument("menu item id is empty".into()));
+    }
+    if item.kind != TrayMenuItemKind::Submenu && !item.items.is_empty() {
+        return Err(AppError::InvalidArgument(format!(
+            "menu item `{}` is not a submenu but has items",
+            item.id
+        )));
+    }
+    item.items.iter().try_for_each(validate)
+}
+
+/// 新菜单项（含子菜单项）中与已有菜单项或彼此重复的 id
+fn first_duplicate(items: &[TrayMenuItem], item: &TrayMenuItem) -> Option<String> {
+    let mut seen: Vec<&str> = Vec::new();
+    collect_ids(items, &mut seen);
+    let mut new = Vec::new();
+    collect_ids(std::slice::from_ref(item), &mut new);
+    for id in new {
+        if seen.contains(&id) {
+            return Some(id.to_string());
+        }
+        seen.push(id);
+    }
+    None
+}
+
+fn collect_ids<'a>(items: &'a [TrayMenuItem], ids: &mut Vec<&'a str>) {
+    for item in items {
+        ids.push(&item.id);
+        collect_ids(&item.items, ids);
+    }
+}
+
+fn find<'a>(items: &'a [TrayMenuItem], id: &str) -> Option<&'a TrayMenuItem> {
+    items.iter().find_map(|item| {
+        if item.id == id {
+            Some(item)
+        } else {
+            find(&item.items, id)
+        }
+    })
+}
+
+fn find_mut<'a>(items: &'a mut [TrayMenuItem], id: &str) -> Option<&'a mut TrayMenuItem> {
+    for item in items {
+        if item.id == id {
+            return Some(item);
+        }
+        if let Some(found) = find_mut(&mut item.items, id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn remove_from(items: &mut Vec<TrayMenuItem>, id: &str) -> Option<TrayMenuItem> {
+    if let Some(index) = items.iter().position(|item| item.id == id) {
+        return Some(items.remove(index));
+    }
+    items
+        .iter_mut()
+        .find_map(|item| remove_from(&mut item.items, id))
+}
+
+/// 内置菜单项不允许前端修改
+fn ensure_editable(id: &str) -> AppResult<()> {
+    if BUILTIN_ITEMS.contains(&id) {
+        return Err(AppError::PermissionDenied(format!(
+            "menu item `{}` is built in",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// 前端注册的菜单项（含子菜单项）不能使用内置菜单项或应用菜单项的 id
+fn ensure_unreserved(item: &TrayMenuItem) -> AppResult<()> {
+    if BUILTIN_ITEMS.contains(&item.id.as_str()) || APP_MENU_ITEMS.contains(&item.id.as_str()) {
+        return Err(AppError::PermissionDenied(format!(
+            "menu item id `{}` is reserved",
+            item.id
+        )));
+    }
+    item.items.iter().try_for_each(ensure_unreserved)
+}
+
+/// 前端注册菜单项，顶层菜单项默认插入到“退出”之前
+pub fn add_item(
+    app: &AppHandle,
+    item: TrayMenuItem,
+    parent: Option<&str>,
+    before: Option<&str>,
+) -> AppResult<()> {
+    ensure_unreserved(&item)?;
+    let menu = app.state::<TrayMenu>();
+    let before = before.or(parent.is_none().then_some("quit"));
+    menu.add(item, parent, before)?;
+    rebuild(app)
+}
+
+/// 前端删除菜单项
+pub fn remove_item(app: &AppHandle, id: &str) -> AppResult<()> {
+    ensure_editable(id)?;
+    app.state::<TrayMenu>().remove(id)?;
+    rebuild(app)
+}
+
+/// 前端修改菜单项的文字、可用和勾选状态
+pub fn update_item(
+    app: &AppHandle,
+    id: &str,
+    label: Option<String>,
+    enabled: Option<bool>,
+    checked: Option<bool>,
+) -> AppResult<TrayMenuItem> {
+    ensure_editable(id)?;
+    let item = app.state::<TrayMenu>().update(id, |item| {
+        if let Some(label) = label {
+            item.label = label;
+        }
+        if let Some(enabled) = enabled {
+            item.enabled = enabled;
+        }
+        if let Some(checked) = checked {
+            item.checked = checked;
+        }
+    })?;
+    rebuild(app)?;
+    Ok(item)
+}
+
+/// 设置勾选状态（Rust 端同步内置菜单项时使用）
+pub(crate) fn set_checked(app: &AppHandle, id: &str, checked: bool) -> AppResult<()> {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return Ok(());
+    };
+    menu.update(id, |item| item.checked = checked)?;
+    rebuild(app)
+}
+
+/// 勾选项当前是否勾选
+pub(crate) fn is_checked(app: &AppHandle, id: &str) -> bool {
+    app.try_state::<TrayMenu>()
+        .and_then(|menu| menu.get(id))
+        .is_some_and(|item| item.checked)
+}
+
+/// 处理菜单点击：更新勾选状态并发送 `tray-menu-clicked` 事件，返回 id 是否属于托盘菜单
+///
+/// 菜单事件是全局的，应用菜单的点击也会到达这里，不在模型中的 id 直接忽略。
+/// 原生勾选项在点击时已经切换了勾选状态，这里只同步模型，不重建菜单。
+pub(crate) fn handle_click(app: &AppHandle, id: &str) -> bool {
+    let Some(payload) = app.try_state::<TrayMenu>().and_then(|menu| menu.click(id)) else {
+        return false;
+    };
+    if let Some(emitter) = app.try_state::<EventEmitter>() {
+        if let Err(err) = emitter.emit_tray_menu_clicked(payload) {
+            log::error!("failed to emit tray-menu-clicked: {}", err);
+        }
+    }
+    true
+}
+
+/// 按模型生成原生菜单
+pub fn build_menu(app: &AppHandle, items: &[TrayMenuItem]) -> AppResult<Menu<Wry>> {
+    let items = build_items(app, items)?;
+    let refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|item| item as _).collect();
+    Ok(Menu::with_items(app, &refs)?)
+}
+
+fn build_items(app: &AppHandle, items: &[TrayMenuItem]) -> AppResult<Vec<MenuItemKind<Wry>>> {
+    items
+        .iter()
+        .map(|item| {
+            let accelerator = item.accelerator.as_deref();
+            let native = match item.kind {
+                TrayMenuItemKind::Normal => MenuItemKind::MenuItem(MenuItem::with_id(
+                    app,
+                    &item.id,
+                    &item.label,
+                    item.enabled,
+                    accelerator,
+                )?),
+                TrayMenuItemKind::Check => MenuItemKind::Check(CheckMenuItem::with_id(
+                    app,
+                    &item.id,
+                    &item.label,
+                    item.enabled,
+                    item.checked,
+                    accelerator,
+                )?),
+                TrayMenuItemKind::Submenu => {
+                    let children = build_items(app, &item.items)?;
+                    let refs: Vec<&dyn IsMenuItem<Wry>> =
+                        children.iter().map(|child| child as _).collect();
+                    MenuItemKind::Submenu(Submenu::with_id_and_items(
+                        app,
+                        &item.id,
+                        &item.label,
+                        item.enabled,
+                        &refs,
+                    )?)
+                }
+                TrayMenuItemKind::Separator => {
+                    MenuItemKind::Predefined(PredefinedMenuItem::separator(app)?)
+                }
+            };
+            Ok(native)
+        })
+        .collect()
+}
+
+/// 按当前模型重建托盘菜单
+pub fn rebuild(app: &AppHandle) -> AppResult<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let menu = build_menu(app, &app.state::<TrayMenu>().items())?;
+    tray.set_menu(Some(menu))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu() -> TrayMenu {
+        TrayMenu::new(vec![
+            TrayMenuItem::normal("main", "主窗口"),
+            TrayMenuItem::submenu("more", "更多", vec![TrayMenuItem::normal("a", "A")]),
+            TrayMenuItem::normal("quit", "退出"),
+        ])
+    }
+
+    fn ids(items: &[TrayMenuItem]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn inserts_before_and_into_submenus() {
+        let menu = menu();
+        menu.add(TrayMenuItem::separator("sep"), None, Some("quit"))
+            .unwrap();
+        menu.add(TrayMenuItem::check("b", "B", true), Some("more"), None)
+            .unwrap();
+        let items = menu.items();
+        assert_eq!(ids(&items), ["main", "more", "sep", "quit"]);
+        assert_eq!(ids(&items[1].items), ["a", "b"]);
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_and_invalid_parents() {
+        let menu = menu();
+        let nested = TrayMenuItem::submenu("new", "New", vec![TrayMenuItem::normal("a", "A")]);
+        assert!(menu.add(nested, None, None).is_err());
+        assert!(menu
+            .add(TrayMenuItem::normal("x", "X"), Some("main"), None)
+            .is_err());
+        assert!(menu
+            .add(TrayMenuItem::normal("x", "X"), Some("none"), None)
+            .is_err());
+        assert!(menu.add(TrayMenuItem::normal("", "X"), None, None).is_err());
+        assert_eq!(menu.items().len(), 3);
+    }
+
+    #[test]
+    fn removes_and_updates_nested_items() {
+        let menu = menu();
+        let item = menu.update("a", |item| item.enabled = false).unwrap();
+        assert!(!item.enabled);
+        assert_eq!(menu.remove("a").unwrap().id, "a");
+        assert!(menu.items()[1].items.is_empty());
+        assert!(menu.remove("a").is_err());
+    }
+
+    #[test]
+    fn clicks_toggle_check_items_only() {
+        let menu = menu();
+        menu.add(TrayMenuItem::check("c", "C", false), None, None)
+            .unwrap();
+        let checked = |id| menu.click(id).map(|payload| payload.checked);
+        assert_eq!(checked("c"), Some(Some(true)));
+        assert_eq!(checked("c"), Some(Some(false)));
+        assert_eq!(checked("main"), Some(None));
+    }
+
+    #[test]
+    fn ignores_clicks_outside_the_tray_menu() {
+        let menu = menu();
+        // 应用菜单的点击也会到达托盘的菜单事件
+        for id in ["open", "about", "missing"] {
+            assert!(menu.click(id).is_none());
+        }
+        assert_eq!(menu.click("a").unwrap().id, "a");
+    }
+
+    #[test]
+    fn rejects_reserved_ids() {
+        for id in BUILTIN_ITEMS.iter().chain(APP_MENU_ITEMS) {
+            assert!(ensure_unreserved(&TrayMenuItem::normal(id, "X")).is_err());
+            let nested = TrayMenuItem::submenu("more", "More", vec![TrayMenuItem::normal(id, "X")]);
+            assert!(ensure_unreserved(&nested).is_err());
+        }
+        assert!(ensure_unreserved(&TrayMenuItem::normal("custom", "X")).is_ok());
+    }
+
+    #[test]
+    fn id_is_required_and_other_fields_default() {
+        let item: TrayMenuItem = serde_json::from_str(r#"{ "id": "x" }"#).unwrap();
+        assert_eq!(item, TrayMenuItem::normal("x", ""));
+        assert!(serde_json::from_str::<TrayMenuItem>(r#"{ "label": "X" }"#).is_err());
+    }
+
+    #[test]
+    fn built_in_items_are_not_editable() {
+        for id in BUILTIN_ITEMS {
+            assert!(ensure_editable(id).is_err());
+        }
+        assert!(ensure_editable("custom").is_ok());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::core::error::AppResult;
+use crate::ui::tray::{load_tray_icon, set_tray_base_icon};
+use crate::ui::tray_icon::monochrome;
+use image::{Rgba, RgbaImage};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Theme, WebviewWindow, WindowEvent};
+
+/// 托盘图标变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconVariant {
+    /// 浅色主题使用的深色图标
+    Light,
+    /// 深色主题使用的浅色图标
+    Dark,
+    /// macOS 模板图标，只使用透明度，由系统按菜单栏着色
+    Template,
+}
+
+impl IconVariant {
+    /// 当前系统主题对应的变体；macOS 菜单栏总是使用模板图标
+    pub fn for_theme(theme: Theme) -> Self {
+        if cfg!(target_os = "macos") {
+            return Self::Template;
+        }
+        match theme {
+            Theme::Dark => Self::Dark,
+            _ => Self::Light,
+        }
+    }
+
+    /// 图标文件（相对于资源目录）
+    ///
+    /// 这些文件是可选的，默认不随应用分发；没有时由彩色图标生成，见 [`fallback`]。
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Light => "icons/tray-light.png",
+            Self::Dark => "icons/tray-dark.png",
+            Self::Template => "icons/tray-template.png",
+        }
+    }
+
+    pub fn is_template(self) -> bool {
+        self == Self::Template
+    }
+}
+
+/// 托盘图标主题：彩色图标和当前使用的变体
+pub struct TrayTheme {
+    colour: RgbaImage,
+    variant: Mutex<IconVariant>,
+}
+
+impl TrayTheme {
+    pub fn new(colour: RgbaImage, variant: IconVariant) -> Self {
+        Self {
+            colour,
+            variant: Mutex::new(variant),
+        }
+    }
+}
+
+/// 加载变体图标，没有对应文件时见 [`fallback`]
+pub fn load_variant(app: &AppHandle, variant: IconVariant, colour: &RgbaImage) -> RgbaImage {
+    load_tray_icon(app, variant.file_name()).unwrap_or_else(|err| {
+        log::debug!("{}, generating monochrome tray icon", err);
+        fallback(variant, colour)
+    })
+}
+
+/// 没有变体图标文件时，由彩色图标生成单色图标
+///
+/// 浅色主题用深色，深色主题用浅色；模板图标只使用透明度，颜色由系统决定。
+fn fallback(variant: IconVariant, colour: &RgbaImage) -> RgbaImage {
+    let tint = match variant {
+        IconVariant::Light | IconVariant::Template => Rgba([0x00, 0x00, 0x00, 0xFF]),
+        IconVariant::Dark => Rgba([0xFF, 0xFF, 0xFF, 0xFF]),
+    };
+    monochrome(colour, tint)
+}
+
+/// 按主题切换托盘图标
+pub fn apply_theme(app: &AppHandle, theme: Theme) -> AppResult<()> {
+    let Some(state) = app.try_state::<TrayTheme>() else {
+        return Ok(());
+    };
+    let variant = IconVariant::for_theme(theme);
+    {
+        let mut current = state.variant.lock().unwrap();
+        if *current == variant {
+            return Ok(());
+        }
+        *current = variant;
+    }
+    log::debug!("tray icon variant changed to {:?}", variant);
+
+    let base = load_variant(app, variant, &state.colour);
+    set_tray_base_icon(app, base, variant.is_template())
+}
+
+/// 系统主题变化时切换托盘图标
+pub fn watch_theme(window: &WebviewWindow) {
+    let app = window.app_handle().clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::ThemeChanged(theme) = event {
+            if let Err(err) = apply_theme(&app, *theme) {
+                log::error!("failed to apply tray icon theme: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_follows_theme() {
+        for theme in [Theme::Light, Theme::Dark] {
+            let variant = IconVariant::for_theme(theme);
+            if cfg!(target_os = "macos") {
+                assert_eq!(variant, IconVariant::Template);
+            } else {
+                assert!(!variant.is_template());
+                assert_eq!(variant == IconVariant::Dark, theme == Theme::Dark);
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_to_tinted_monochrome() {
+        let mut colour = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        colour.put_pixel(1, 1, Rgba([0xE5, 0x39, 0x35, 0xFF]));
+        for (variant, tint) in [
+            (IconVariant::Light, [0x00, 0x00, 0x00]),
+            (IconVariant::Dark, [0xFF, 0xFF, 0xFF]),
+            (IconVariant::Template, [0x00, 0x00, 0x00]),
+        ] {
+            let icon = fallback(variant, &colour);
+            assert_ne!(icon, colour, "{:?}", variant);
+            assert_eq!(icon.get_pixel(1, 1).0[..3], tint, "{:?}", variant);
+            assert_eq!(icon.get_pixel(1, 1)[3], 0xFF);
+            assert_eq!(icon.get_pixel(0, 0)[3], 0);
+        }
+    }
+}
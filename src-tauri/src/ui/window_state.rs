@@ -0,0 +1,382 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow, WindowEvent,
+};
+
+/// 窗口状态文件名（与配置文件位于同一目录）
+pub const WINDOW_STATE_FILE_NAME: &str = "window-state.json";
+
+/// 移动、缩放时写盘的最小间隔
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 位置由托盘决定、只恢复尺寸的窗口
+const SIZE_ONLY_WINDOWS: &[&str] = &["popover"];
+
+/// 窗口状态（物理像素）
+///
+/// 最大化时 `x`/`y`/`width`/`height` 保留最大化之前的位置和尺寸。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    /// 窗口所在显示器的名称
+    pub monitor: Option<String>,
+}
+
+/// 矩形区域（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Area {
+    fn of_monitor(monitor: &Monitor) -> Self {
+        let area = monitor.work_area();
+        Self {
+            x: area.position.x,
+            y: area.position.y,
+            width: area.size.width,
+            height: area.size.height,
+        }
+    }
+
+    /// 与另一区域重叠的面积
+    fn overlap(&self, other: &Area) -> u64 {
+        let left = self.x.max(other.x) as i64;
+        let top = self.y.max(other.y) as i64;
+        let right = (self.x as i64 + self.width as i64).min(other.x as i64 + other.width as i64);
+        let bottom = (self.y as i64 + self.height as i64).min(other.y as i64 + other.height as i64);
+        ((right - left).max(0) * (bottom - top).max(0)) as u64
+    }
+}
+
+/// 窗口尺寸限制（物理像素）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimits {
+    pub min: (u32, u32),
+    pub max: (u32, u32),
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        Self {
+            min: (0, 0),
+            max: (u32::MAX, u32::MAX),
+        }
+    }
+}
+
+/// 将保存的窗口区域限制在尺寸范围和显示器工作区内
+///
+/// 尺寸先按 `limits` 限制，再缩小到不超过工作区；位置平移到窗口完全位于工作区内。
+pub fn clamp_to_area(window: Area, area: Area, limits: SizeLimits) -> Area {
+    let width = window
+        .width
+        .clamp(limits.min.0, limits.max.0.max(limits.min.0))
+        .min(area.width);
+    let height = window
+        .height
+        .clamp(limits.min.1, limits.max.1.max(limits.min.1))
+        .min(area.height);
+    let max_x = area.x + (area.width - width) as i32;
+    let max_y = area.y + (area.height - height) as i32;
+    Area {
+        x: window.x.clamp(area.x, max_x),
+        y: window.y.clamp(area.y, max_y),
+        width,
+        height,
+    }
+}
+
+/// 所有窗口的状态，按窗口 label 保存到 `window-state.json`
+pub struct WindowStateStore {
+    path: PathBuf,
+    states: Mutex<HashMap<String, WindowState>>,
+    last_save: Mutex<Option<Instant>>,
+}
+
+impl WindowStateStore {
+    /// 从指定路径加载，文件缺失或损坏时为空
+    pub fn load(path: PathBuf) -> Self {
+        let states = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                log::warn!("invalid window state {}: {}", path.display(), err);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path,
+            states: Mutex::new(states),
+            last_save: Mutex::new(None),
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<WindowState> {
+        self.states.lock().unwrap().get(label).cloned()
+    }
+
+    fn set(&self, label: &str, state: WindowState) {
+        self.states.lock().unwrap().insert(label.to_string(), state);
+    }
+
+    /// 写回磁盘
+    pub fn save(&self) -> io::Result<()> {
+        let json = {
+            let states = self.states.lock().unwrap();
+            serde_json::to_string_pretty(&*states).map_err(io::Error::other)?
+        };
+        *self.last_save.lock().unwrap() = Some(Instant::now());
+        write_atomic(&self.path, &json)
+    }
+
+    /// 距上次写盘超过 `SAVE_INTERVAL` 时写回磁盘
+    fn save_throttled(&self) {
+        let due = self
+            .last_save
+            .lock()
+            .unwrap()
+            .is_none_or(|last| last.elapsed() >= SAVE_INTERVAL);
+        if due {
+            self.save_logged();
+        }
+    }
+
+    fn save_logged(&self) {
+        if let Err(err) = self.save() {
+            log::error!("failed to save window state: {}", err);
+        }
+    }
+}
+
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    {
+        let mut tmp = fs::File::create(&tmp_path)?;
+        tmp.write_all(contents.as_bytes())?;
+        tmp.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+/// 恢复窗口状态，并在移动、缩放、关闭时保存
+pub fn track_window(window: &WebviewWindow) {
+    restore_window(window);
+
+    let tracked = window.clone();
+    window.on_window_event(move |event| {
+        let Some(store) = tracked.try_state::<WindowStateStore>() else {
+            return;
+        };
+        match event {
+            WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+                capture_window(&tracked, &store);
+                store.save_throttled();
+            }
+            WindowEvent::CloseRequested { .. } | WindowEvent::Focused(false) => {
+                capture_window(&tracked, &store);
+                store.save_logged();
+            }
+            _ => {}
+        }
+    });
+}
+
+/// 保存所有窗口的当前状态（退出应用前调用）
+pub fn save_all(app: &AppHandle) {
+    let Some(store) = app.try_state::<WindowStateStore>() else {
+        return;
+    };
+    for window in app.webview_windows().values() {
+        capture_window(window, &store);
+    }
+    store.save_logged();
+}
+
+/// 记录窗口当前状态；窗口隐藏或最小化时不记录
+fn capture_window(window: &WebviewWindow, store: &WindowStateStore) {
+    let label = window.label();
+    if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    let previous = store.get(label);
+
+    // 最大化时保留之前的位置和尺寸，恢复后取消最大化仍回到原处
+    let (x, y, width, height) = match (&previous, maximized) {
+        (Some(previous), true) => (previous.x, previous.y, previous.width, previous.height),
+        _ => {
+            let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                return;
+            };
+            (position.x, position.y, size.width, size.height)
+        }
+    };
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|monitor| monitor.name().cloned());
+
+    store.set(
+        label,
+        WindowState {
+            x,
+            y,
+            width,
+            height,
+            maximized,
+            monitor,
+        },
+    );
+}
+
+/// 恢复保存的状态，位置限制在当前存在的显示器内
+fn restore_window(window: &WebviewWindow) {
+    let Some(store) = window.try_state::<WindowStateStore>() else {
+        return;
+    };
+    let Some(state) = store.get(window.label()) else {
+        return;
+    };
+    let monitors = window.available_monitors().unwrap_or_default();
+    let saved = Area {
+        x: state.x,
+        y: state.y,
+        width: state.width,
+        height: state.height,
+    };
+    let Some(monitor) = target_monitor(&state, saved, &monitors) else {
+        return;
+    };
+
+    let limits = size_limits(window, monitor.scale_factor());
+    let area = clamp_to_area(saved, Area::of_monitor(monitor), limits);
+    if window.is_resizable().unwrap_or(false) {
+        let _ = window.set_size(PhysicalSize::new(area.width, area.height));
+    }
+    if SIZE_ONLY_WINDOWS.contains(&window.label()) {
+        return;
+    }
+    let _ = window.set_position(PhysicalPosition::new(area.x, area.y));
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// 恢复到哪个显示器：优先使用保存的显示器，其次是与保存区域重叠最多的显示器
+fn target_monitor<'a>(
+    state: &WindowState,
+    saved: Area,
+    monitors: &'a [Monitor],
+) -> Option<&'a Monitor> {
+    if let Some(name) = &state.monitor {
+        if let Some(monitor) = monitors.iter().find(|m| m.name() == Some(name)) {
+            return Some(monitor);
+        }
+    }
+    monitors
+        .iter()
+        .max_by_key(|monitor| Area::of_monitor(monitor).overlap(&saved))
+}
+
+/// `tauri.conf.json` 中声明的尺寸限制（逻辑像素转为物理像素）
+fn size_limits(window: &WebviewWindow, scale_factor: f64) -> SizeLimits {
+    let Some(config) = window
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|config| config.label == window.label())
+    else {
+        return SizeLimits::default();
+    };
+    let physical = |value: Option<f64>, default: u32| {
+        value.map_or(default, |value| (value * scale_factor).round() as u32)
+    };
+    SizeLimits {
+        min: (
+            physical(config.min_width, 0),
+            physical(config.min_height, 0),
+        ),
+        max: (
+            physical(config.max_width, u32::MAX),
+            physical(config.max_height, u32::MAX),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Area = Area {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    #[test]
+    fn keeps_windows_that_fit() {
+        let window = Area {
+            x: 100,
+            y: 200,
+            width: 800,
+            height: 600,
+        };
+        assert_eq!(clamp_to_area(window, SCREEN, SizeLimits::default()), window);
+    }
+
+    #[test]
+    fn moves_windows_from_removed_monitors_back_on_screen() {
+        let window = Area {
+            x: 2500,
+            y: -300,
+            width: 800,
+            height: 600,
+        };
+        let clamped = clamp_to_area(window, SCREEN, SizeLimits::default());
+        assert_eq!((clamped.x, clamped.y), (1120, 0));
+    }
+
+    #[test]
+    fn applies_size_limits_and_screen_size() {
+        let limits = SizeLimits {
+            min: (300, 300),
+            max: (600, 800),
+        };
+        let tiny = Area {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+        };
+        let clamped = clamp_to_area(tiny, SCREEN, limits);
+        assert_eq!((clamped.width, clamped.height), (300, 300));
+
+        let huge = Area {
+            x: 0,
+            y: 0,
+            width: 4000,
+            height: 4000,
+        };
+        let clamped = clamp_to_area(huge, SCREEN, SizeLimits::default());
+        assert_eq!((clamped.width, clamped.height), (1920, 1080));
+    }
+}
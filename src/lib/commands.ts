@@ -1,6 +1,84 @@
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
+
+// <generated:commands>
+// 由 src-tauri 生成，请勿手动修改
+/** 应用初始化配置 */
+export interface AppConfig {
+  autostart_config?: AutostartConfig;
+  autostart_enabled?: boolean;
+  plugins?: PluginsConfig;
+}
+
+/** 命令失败时返回的结构化错误 */
+export interface AppError {
+  code: "TAURI_ERROR" | "IO_ERROR" | "AUTOSTART_ERROR" | "SERIALIZE_ERROR" | "CONFIG_ERROR" | "INVALID_ARGUMENT" | "PERMISSION_DENIED" | "PLUGIN_DISABLED" | "NOT_FOUND" | "INTERNAL_ERROR";
+  details?: string;
+  message: string;
+}
+
+/** 自动启动配置 */
+export interface AutostartConfig {
+  args?: string[] | null;
+  /** 自动启动后延迟多少秒再开始后台任务，避免拖慢登录 */
+  delay_secs?: number;
+  launcher?: MacosLauncher;
+  /** 已有实例在运行时直接退出，不唤起已有实例 */
+  skip_if_running?: boolean;
+  /** 自动启动时隐藏所有窗口，只显示托盘 */
+  start_hidden?: boolean;
+}
+
+/** 向系统注册自动启动的结果 */
+export interface AutostartStatus {
+  /** 系统中实际的注册状态 */
+  enabled: boolean;
+  /** 注册前发现并已修复的差异，例如程序被移动后自动启动文件仍指向旧路径（仅 Linux） */
+  repaired: string[];
+}
+
+/** 单个命令的调用统计 */
+export interface CommandMetrics {
+  /** 成功分发的调用次数 */
+  calls: number;
+  command: string;
+  /** 最近一次调用来自的窗口 */
+  last_window: string | null;
+  /** 单次最长耗时（毫秒） */
+  max_ms: number;
+  /** 因权限或参数校验被拒绝的次数 */
+  rejected: number;
+  /** 累计耗时（毫秒） */
+  total_ms: number;
+}
+
+/** 配置变更事件负载 */
+export interface ConfigChangedPayload {
+  /** 发生变化的顶层字段名 */
+  changed: string[];
+  /** 变更后的完整配置 */
+  config: AppConfig;
+}
+
+/** 崩溃报告 */
+export interface CrashReport {
+  backtrace: string;
+  id: string;
+  location: string | null;
+  message: string;
+  recent_logs: string[];
+  thread: string | null;
+  timestamp: string;
+  version: string;
+}
+
+/** 通过 channel 发送给前端的应用事件 */
+export interface EventMessage {
+  /** 事件名 */
+  event: string;
+  /** 事件负载 */
+  payload: unknown;
+}
 
-// 定义命令参数和返回值的类型
 export interface GreetParams {
   name: string;
 }
@@ -9,6 +87,72 @@ export interface GreetResponse {
   message: string;
 }
 
+/** 一条日志记录 */
+export interface LogEntry {
+  level: string;
+  message: string;
+  target: string;
+  timestamp: string;
+}
+
+/** macOS 自动启动方式 */
+export type MacosLauncher = "launch_agent" | "apple_script";
+
+/** 插件开关（可在配置文件中关闭某个插件） */
+export interface PluginsConfig {
+  autostart?: boolean;
+  opener?: boolean;
+}
+
+/** 第二个实例启动事件负载 */
+export interface SecondInstancePayload {
+  /** 第二个实例的命令行参数（不包含程序名） */
+  args: string[];
+  /** 第二个实例的工作目录 */
+  cwd: string;
+}
+
+/** 托盘动画参数 */
+export interface TrayAnimationOptions {
+  /** 帧率，限制在 1 到 60 之间 */
+  fps?: number;
+  /** 精灵图每帧的高度 */
+  frame_height?: number | null;
+  /** 精灵图每帧的宽度，与 `frame_height` 同时指定时按从左到右、从上到下切分 */
+  frame_width?: number | null;
+  /** 动画文件（GIF、APNG 或精灵图），相对于资源目录；不指定时使用内置的加载动画 */
+  source?: string | null;
+}
+
+/** 托盘菜单点击事件负载 */
+export interface TrayMenuClickedPayload {
+  /** 勾选项点击后的勾选状态，其他菜单项为 `null` */
+  checked: boolean | null;
+  /** 菜单项 id */
+  id: string;
+}
+
+/** 托盘菜单项 */
+export interface TrayMenuItem {
+  /** 快捷键，例如 `CmdOrCtrl+Q` */
+  accelerator?: string | null;
+  /** 勾选状态（仅 `check`） */
+  checked?: boolean;
+  enabled?: boolean;
+  /** 唯一 id，点击时随 `tray-menu-clicked` 事件发送 */
+  id: string;
+  /** 子菜单项（仅 `submenu`） */
+  items?: TrayMenuItem[];
+  kind?: TrayMenuItemKind;
+  label?: string;
+}
+
+/** 菜单项类型 */
+export type TrayMenuItemKind = "normal" | "check" | "submenu" | "separator";
+
+/** 托盘图标状态（前端 `set_tray_status` 传入） */
+export type TrayState = "idle" | "busy" | "error" | "unread" | "paused";
+
 // 命令调用封装类
 export class Commands {
   /**
@@ -17,7 +161,160 @@ export class Commands {
   static async greet(params: GreetParams): Promise<GreetResponse> {
     return await invoke<GreetResponse>("greet", { params });
   }
+
+  /**
+   * 获取持久化配置
+   */
+  static async getSettings(): Promise<AppConfig> {
+    return await invoke<AppConfig>("get_settings");
+  }
+
+  /**
+   * 更新配置并写回磁盘
+   */
+  static async setSettings(settings: AppConfig): Promise<AppConfig> {
+    return await invoke<AppConfig>("set_settings", { settings });
+  }
+
+  /**
+   * 获取自动启动设置
+   */
+  static async getAutostart(): Promise<boolean> {
+    return await invoke<boolean>("get_autostart");
+  }
+
+  /**
+   * 开启或关闭自动启动，返回系统中的实际注册状态和修复的差异
+   */
+  static async setAutostart(enabled: boolean): Promise<AutostartStatus> {
+    return await invoke<AutostartStatus>("set_autostart", { enabled });
+  }
+
+  /**
+   * 获取最近的日志
+   */
+  static async getRecentLogs(limit?: number): Promise<LogEntry[]> {
+    return await invoke<LogEntry[]>("get_recent_logs", { limit });
+  }
+
+  /**
+   * 获取当前日志级别
+   */
+  static async getLogLevel(): Promise<string> {
+    return await invoke<string>("get_log_level");
+  }
+
+  /**
+   * 运行时调整日志级别
+   */
+  static async setLogLevel(level: string): Promise<void> {
+    return await invoke<void>("set_log_level", { level });
+  }
+
+  /**
+   * 将前端日志写入应用日志
+   */
+  static async writeLog(level: string, message: string): Promise<void> {
+    return await invoke<void>("write_log", { level, message });
+  }
+
+  /**
+   * 获取尚未查看的崩溃报告
+   */
+  static async getCrashReports(): Promise<CrashReport[]> {
+    return await invoke<CrashReport[]>("get_crash_reports");
+  }
+
+  /**
+   * 将崩溃报告标记为已查看，不传 ids 时处理全部
+   */
+  static async dismissCrashReports(ids?: string[]): Promise<void> {
+    return await invoke<void>("dismiss_crash_reports", { ids });
+  }
+
+  /**
+   * 获取各命令的调用统计
+   */
+  static async getCommandMetrics(): Promise<CommandMetrics[]> {
+    return await invoke<CommandMetrics[]>("get_command_metrics");
+  }
+
+  /**
+   * 获取托盘菜单
+   */
+  static async getTrayMenu(): Promise<TrayMenuItem[]> {
+    return await invoke<TrayMenuItem[]>("get_tray_menu");
+  }
+
+  /**
+   * 注册托盘菜单项，`parent` 为子菜单 id，`before` 为插入位置（默认在“退出”之前）
+   */
+  static async addTrayMenuItem(item: TrayMenuItem, parent?: string, before?: string): Promise<void> {
+    return await invoke<void>("add_tray_menu_item", { item, parent, before });
+  }
+
+  /**
+   * 删除托盘菜单项（内置菜单项不能删除）
+   */
+  static async removeTrayMenuItem(id: string): Promise<void> {
+    return await invoke<void>("remove_tray_menu_item", { id });
+  }
+
+  /**
+   * 修改托盘菜单项的文字、可用和勾选状态，未传入的字段保持不变
+   */
+  static async updateTrayMenuItem(id: string, label?: string, enabled?: boolean, checked?: boolean): Promise<TrayMenuItem> {
+    return await invoke<TrayMenuItem>("update_tray_menu_item", { id, label, enabled, checked });
+  }
+
+  /**
+   * 切换托盘图标状态，`count` 为未读数（仅 `unread`）
+   */
+  static async setTrayStatus(state: TrayState, count?: number): Promise<void> {
+    return await invoke<void>("set_tray_status", { state, count });
+  }
+
+  /**
+   * 开始播放托盘图标动画（GIF、APNG、精灵图或内置加载动画），替换正在播放的动画
+   */
+  static async startTrayAnimation(options: TrayAnimationOptions): Promise<void> {
+    return await invoke<void>("start_tray_animation", { options });
+  }
+
+  /**
+   * 停止托盘图标动画并恢复为当前状态的图标
+   */
+  static async stopTrayAnimation(): Promise<void> {
+    return await invoke<void>("stop_tray_animation");
+  }
+
+  /**
+   * 订阅应用事件，只会收到当前窗口有权接收的事件（由 `eventManager` 调用）
+   */
+  static async subscribeEvents(channel: Channel<EventMessage>): Promise<void> {
+    return await invoke<void>("subscribe_events", { channel });
+  }
 }
 
 // 导出便捷方法
 export const greet = Commands.greet;
+export const getSettings = Commands.getSettings;
+export const setSettings = Commands.setSettings;
+export const getAutostart = Commands.getAutostart;
+export const setAutostart = Commands.setAutostart;
+export const getRecentLogs = Commands.getRecentLogs;
+export const getLogLevel = Commands.getLogLevel;
+export const setLogLevel = Commands.setLogLevel;
+export const writeLog = Commands.writeLog;
+export const getCrashReports = Commands.getCrashReports;
+export const dismissCrashReports = Commands.dismissCrashReports;
+export const getCommandMetrics = Commands.getCommandMetrics;
+export const getTrayMenu = Commands.getTrayMenu;
+export const addTrayMenuItem = Commands.addTrayMenuItem;
+export const removeTrayMenuItem = Commands.removeTrayMenuItem;
+export const updateTrayMenuItem = Commands.updateTrayMenuItem;
+export const setTrayStatus = Commands.setTrayStatus;
+export const startTrayAnimation = Commands.startTrayAnimation;
+export const stopTrayAnimation = Commands.stopTrayAnimation;
+export const subscribeEvents = Commands.subscribeEvents;
+// </generated:commands>
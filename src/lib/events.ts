@@ -1,17 +1,38 @@
-import {listen, UnlistenFn, Event} from "@tauri-apps/api/event";
+import {UnlistenFn} from "@tauri-apps/api/event";
+import {Channel} from "@tauri-apps/api/core";
+import {EventMessage, subscribeEvents} from "./commands";
+
+// 应用事件
+export interface AppEvent<T> {
+    event: string;
+    payload: T;
+}
 
 // 事件监听器类型
-type EventCallback<T> = (event: Event<T>) => void;
+export type EventCallback<T> = (event: AppEvent<T>) => void;
 
 // 事件管理器类
+//
+// 应用事件通过 `subscribe_events` 注册的 channel 接收，而不是全局事件总线：
+// 后端只向有权接收的窗口发送，其他窗口的监听器不会被调用。
 export class EventManager {
     private listeners: Map<string, UnlistenFn[]> = new Map();
+    private callbacks: Map<string, Set<EventCallback<unknown>>> = new Map();
+    private subscription: Promise<void> | null = null;
 
     /**
      * 通用事件监听方法
      */
     async on<T>(event: string, callback: EventCallback<T>): Promise<UnlistenFn> {
-        const unlisten = await listen<T>(event, callback);
+        await this.subscribe();
+        const handler = callback as EventCallback<unknown>;
+        if (!this.callbacks.has(event)) {
+            this.callbacks.set(event, new Set());
+        }
+        this.callbacks.get(event)!.add(handler);
+        const unlisten = () => {
+            this.callbacks.get(event)?.delete(handler);
+        };
         this.addListener(event, unlisten);
         return unlisten;
     }
@@ -37,6 +58,25 @@ export class EventManager {
         this.listeners.clear();
     }
 
+    /**
+     * 每个窗口只订阅一次，失败时下次监听重试
+     */
+    private subscribe(): Promise<void> {
+        if (!this.subscription) {
+            const channel = new Channel<EventMessage>();
+            channel.onmessage = (message) => this.dispatch(message);
+            this.subscription = subscribeEvents(channel).catch((error) => {
+                this.subscription = null;
+                throw error;
+            });
+        }
+        return this.subscription;
+    }
+
+    private dispatch(message: EventMessage): void {
+        this.callbacks.get(message.event)?.forEach((callback) => callback(message));
+    }
+
     private addListener(event: string, unlisten: UnlistenFn): void {
         if (!this.listeners.has(event)) {
             this.listeners.set(event, []);
@@ -47,3 +87,24 @@ export class EventManager {
 
 // 导出单例
 export const eventManager = new EventManager();
+
+// <generated:events>
+// 由 src-tauri 生成，请勿手动修改
+import type { ConfigChangedPayload, SecondInstancePayload, TrayMenuClickedPayload } from "./commands";
+
+export const CONFIG_CHANGED_EVENT = "config-changed";
+
+export const onConfigChanged = (callback: EventCallback<ConfigChangedPayload>): Promise<UnlistenFn> =>
+    eventManager.on<ConfigChangedPayload>(CONFIG_CHANGED_EVENT, callback);
+
+export const SECOND_INSTANCE_EVENT = "second-instance";
+
+export const onSecondInstance = (callback: EventCallback<SecondInstancePayload>): Promise<UnlistenFn> =>
+    eventManager.on<SecondInstancePayload>(SECOND_INSTANCE_EVENT, callback);
+
+export const TRAY_MENU_CLICKED_EVENT = "tray-menu-clicked";
+
+export const onTrayMenuClicked = (callback: EventCallback<TrayMenuClickedPayload>): Promise<UnlistenFn> =>
+    eventManager.on<TrayMenuClickedPayload>(TRAY_MENU_CLICKED_EVENT, callback);
+
+// </generated:events>
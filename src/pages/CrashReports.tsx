@@ -0,0 +1,60 @@
+import { useEffect, useState } from "react";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { Button, Collapse, Empty, Space } from "antd";
+import { Commands, CrashReport } from "../lib/commands";
+
+/**
+ * 崩溃报告页面
+ * 上次运行崩溃时在启动后自动打开
+ */
+function CrashReports() {
+  const [reports, setReports] = useState<CrashReport[]>([]);
+
+  useEffect(() => {
+    Commands.getCrashReports().then(setReports);
+  }, []);
+
+  const dismissAll = async () => {
+    await Commands.dismissCrashReports();
+    await getCurrentWindow().close();
+  };
+
+  return (
+    <div className="p-6">
+      <h1 className="text-2xl mb-2 text-gray-800">应用上次意外退出</h1>
+      <div className="text-sm text-gray-500 mb-5">
+        以下是崩溃报告，可以复制后反馈给开发者。
+      </div>
+      {reports.length === 0 ? (
+        <Empty description="没有崩溃报告" />
+      ) : (
+        <Collapse
+          items={reports.map((report) => ({
+            key: report.id,
+            label: `${report.timestamp}  ${report.message}`,
+            children: (
+              <pre className="text-xs whitespace-pre-wrap break-all">
+                {[
+                  `版本：${report.version}`,
+                  `位置：${report.location ?? "未知"}`,
+                  `线程：${report.thread ?? "未知"}`,
+                  "",
+                  report.backtrace,
+                  "",
+                  ...report.recent_logs,
+                ].join("\n")}
+              </pre>
+            ),
+          }))}
+        />
+      )}
+      <Space className="mt-5">
+        <Button type="primary" onClick={dismissAll}>
+          我知道了
+        </Button>
+      </Space>
+    </div>
+  );
+}
+
+export default CrashReports;
@@ -5,6 +5,7 @@
 export const ROUTES = {
   HOME: "/",
   ABOUT: "/about",
+  CRASH_REPORTS: "/crash-reports",
   POPOVER: "/popover",
 } as const;
 
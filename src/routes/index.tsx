@@ -2,6 +2,7 @@ import { Routes, Route } from "react-router-dom";
 import Layout from "../components/Layout";
 import App from "../App";
 import About from "../pages/About";
+import CrashReports from "../pages/CrashReports";
 import Popover from "../pages/Popover";
 import { ROUTES } from "./config";
 
@@ -14,6 +15,7 @@ export default function AppRoutes() {
       <Route path={ROUTES.HOME} element={<Layout />}>
         <Route index element={<App />} />
         <Route path="about" element={<About />} />
+        <Route path="crash-reports" element={<CrashReports />} />
       </Route>
       {/* Popover 路由，不使用 Layout */}
       <Route path={ROUTES.POPOVER} element={<Popover />} />